@@ -1,6 +1,4 @@
 use benchmark::run_test;
-use sql_helpers::create_new_position;
-use engines::{Board, squares::*, turn::*};
 
 mod sql_helpers;
 mod benchmark;
@@ -18,16 +16,18 @@ fn main() {
         depth += 1;
     }
 
-    // let board = Board{
-    //     blocks: [1, 4, 0, 3, 2,
-    //              3, 0, 0, 2, 3,
-    //              4, 0, 0, 0, 2,
-    //              0, 0, 4, 0, 0,
-    //              2, 0, 1, 1, 0],
-    //     workers: [C3, C2, B2, D2],
-    //     turn: W,
-    // };
+    // use engines::*;
+    // let board = Board::new_custom(
+    //     [T1, T4, T0, T3, T2,
+    //      T3, T0, T0, T2, T3,
+    //      T4, T0, T0, T0, T2,
+    //      T0, T0, T4, T0, T0,
+    //      T2, T0, T1, T1, T0],
+    //     [C3, C2],
+    //     [B2, D2],
+    //     P1,
+    // );
 
-    // create_new_position(board, 'F');
+    // sql_helpers::create_new_position(board, 'F');
 
 }
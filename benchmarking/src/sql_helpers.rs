@@ -1,6 +1,6 @@
 use chrono::prelude::*;
 
-use engines::Board;
+use engines::{Blocks, Board, SearchLimits, Square, Turn, Worker};
 use rusqlite::{Connection, Result, params, types::Value};
 pub fn hash_workers(workers:[usize;4]) -> usize{
     let mut sum:usize = 0;
@@ -14,8 +14,8 @@ pub fn hash_workers(workers:[usize;4]) -> usize{
 pub fn unhash_workers(mut hash: usize) -> [usize; 4] {
     let mut w: [usize; 4] = [0; 4]; // Initialize the array with zeros
     let base: usize = 25;
-    for i in 0..4 {
-        w[i] = hash % base;
+    for square in w.iter_mut() {
+        *square = hash % base;
         hash /= base;
     }
     w
@@ -62,16 +62,28 @@ pub fn create_new_position (board: Board, tipo:char) -> Result<()> {
     // Open a connection to the SQLite database file
     let conn = get_connection()?;
 
-    // Convert the blocks array to a string of 25 chars
-    let blocks_str: String = board.blocks.iter().map(|&b| char::from(b)).collect();
+    let request = board.get_request(SearchLimits::default());
 
-    // Convert workers to their respective hash strings
-    let workers = hash_workers(board.workers);
+    // Convert the blocks array to a string of 25 chars
+    let blocks_str: String = request.blocks.iter().map(|&b| char::from(Into::<usize>::into(b) as u8)).collect();
+
+    // Convert workers to their respective hash strings, P1 workers first
+    let mut workers_p1 = vec![];
+    let mut workers_p2 = vec![];
+    for square in Square::squares() {
+        match request.workers[square] {
+            Some(Worker { turn: Turn::P1 }) => workers_p1.push(square.into()),
+            Some(Worker { turn: Turn::P2 }) => workers_p2.push(square.into()),
+            None => (),
+        }
+    }
+    let workers = hash_workers([workers_p1[0], workers_p1[1], workers_p2[0], workers_p2[1]]);
+    let turn: usize = (*request.turn).into();
 
     // Insert data into the table
     conn.execute(
         "INSERT INTO TB_POSITION (vl_blocks, vl_workers, cd_tipo, vl_turno) VALUES (?1, ?2, ?3, ?4)",
-        [&blocks_str, &(workers.to_string()), &(tipo.to_string()), &(board.turn.to_string())],
+        [&blocks_str, &(workers.to_string()), &(tipo.to_string()), &(turn.to_string())],
     )?;
     Ok(())
 }
@@ -95,22 +107,23 @@ pub fn read_position_from_id(position_id: usize) -> Result<Board> {
     let workers_hash:usize = row.get(1)?;
     let turn: usize = row.get(3)?;
 
-    let blocks: [u8; 25] = {
-        let mut array = [0; 25]; // Initialize an array of size 25 with zeros
+    let blocks: [Blocks; 25] = {
+        let mut array = [Blocks::T0; 25]; // Initialize an array of size 25 with zeros
         for (index, &byte) in blocks_str.as_bytes().iter().take(25).enumerate() {
-            array[index] = byte;
+            array[index] = Blocks::try_from(byte as usize).unwrap();
         }
         array
     };    
-    // Convert workers_str back to a vector of Worker
-    let workers = unhash_workers(workers_hash);
+    // Convert workers_str back to squares
+    let workers = unhash_workers(workers_hash).map(|x| Square::try_from(x).unwrap());
 
     // Create a Board instance
-    let board = Board {
+    let board = Board::new_custom(
         blocks,
-        workers,
-        turn: turn as u8, // You might want to handle parsing errors
-    };
+        [workers[0], workers[1]],
+        [workers[2], workers[3]],
+        Turn::try_from(turn).unwrap(), // You might want to handle parsing errors
+    );
 
     Ok(board) 
 }
@@ -1,3 +1,11 @@
+mod server;
+
+use std::io::{stdin, stdout, BufRead};
+
+use crate::server::Server;
+
+// Runs a registry engine as a standalone program speaking the text protocol of
+// engines::protocol on stdin and stdout.
 fn main() {
     let mut args: Vec<String> = std::env::args().collect();
     if args.len() < 2 {
@@ -5,7 +13,25 @@ fn main() {
         return;
     }
 
-    let _engine_name = args.swap_remove(1);
+    let engine_name = args.swap_remove(1);
+
+    let mut server = match Server::new(&engine_name, stdout()) {
+        Ok(server) => server,
+        Err(message) => {
+            println!("{}", message);
+            return;
+        }
+    };
+
+    for line in stdin().lock().lines() {
+        let Ok(line) = line else {
+            break;
+        };
+        if !server.handle(&line) {
+            return;
+        }
+    }
 
-    
+    // The input was closed without quit, so let the last search answer
+    server.finish();
 }
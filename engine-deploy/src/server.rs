@@ -0,0 +1,457 @@
+use engines::handle::SearchHandle;
+use engines::protocol::*;
+use engines::*;
+use std::{
+    io::Write,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+    thread::{self, JoinHandle},
+    time::Duration,
+};
+
+// Clock assumed when `go` doesn't limit the search
+const DEFAULT_TIME: Duration = Duration::from_secs(60);
+// How often an infinite search that has already answered looks for stop
+const STOP_POLL: Duration = Duration::from_millis(10);
+
+fn send<W: Write>(out: &Mutex<W>, response: Response) {
+    let mut out = out.lock().unwrap();
+    let _ = writeln!(out, "{}", response);
+    let _ = out.flush();
+}
+
+// What the engine has to say about a finished search, ending with bestmove. With multipv the
+// first line is about the best move and one more follows for each of the other root moves.
+fn search_responses(result: SearchResult) -> Vec<Response> {
+    let SearchResult { mv, eval, pv, depth_searched, time_spent, root_moves } = result;
+    let multi_pv = root_moves.len() > 1;
+    let info = Info {
+        multipv: multi_pv.then_some(1),
+        depth: depth_searched,
+        score: eval,
+        time: time_spent,
+        pv,
+        ..Default::default()
+    };
+
+    let mut responses = vec![Response::Info(info)];
+    if multi_pv {
+        for (i, root_move) in root_moves.into_iter().enumerate().skip(1) {
+            responses.push(Response::Info(Info {
+                multipv: Some(i + 1),
+                depth: depth_searched,
+                score: Some(root_move.eval),
+                pv: Some(root_move.pv),
+                ..Default::default()
+            }));
+        }
+    }
+    responses.push(Response::BestMove(Some(Ply::Move(mv))));
+    responses
+}
+
+fn placement_responses(result: PlacementResult) -> Vec<Response> {
+    let PlacementResult { squares, eval, time_spent } = result;
+    let info = Info {
+        score: eval,
+        time: time_spent,
+        ..Default::default()
+    };
+    vec![Response::Info(info), Response::BestMove(Some(Ply::Place(squares)))]
+}
+
+// A fresh engine with the options that were set on the previous one
+fn new_engine(name: &str, options: &[(String, String)]) -> Box<dyn Engine> {
+    let mut engine = get_engine(name).expect("The engine was found in the registry before");
+    for (name, value) in options {
+        let _ = engine.set_option(name, value);
+    }
+    engine
+}
+
+struct RunningSearch {
+    control: Arc<SearchControl>,
+    // Only stop ends these, so they are stopped when the input closes
+    infinite: bool,
+    // Set once the responses are about to be written, after which the client may send more
+    answered: Arc<AtomicBool>,
+    // Writes the responses once the search answers and gives the engine back
+    waiter: JoinHandle<Box<dyn Engine>>,
+}
+
+// Serves one registry engine over the text protocol. Searches run on their own thread so
+// commands such as isready and stop are still answered while the engine thinks.
+pub struct Server<W: Write + Send + 'static> {
+    name: String,
+    // None while the engine is searching
+    engine: Option<Box<dyn Engine>>,
+    search: Option<RunningSearch>,
+    game: GameState,
+    // Options the engine accepted, given again to the new engine after newgame or a crash
+    options: Arc<Vec<(String, String)>>,
+    out: Arc<Mutex<W>>,
+}
+impl<W: Write + Send + 'static> Server<W> {
+    pub fn new(name: &str, out: W) -> Result<Self, String> {
+        let engine = get_engine(name)
+            .ok_or(format!("Unknown engine {}! Available: {:?}", name, get_engine_names()))?;
+
+        Ok(Server {
+            name: name.to_string(),
+            engine: Some(engine),
+            search: None,
+            game: GameState::new(Board::empty()),
+            options: Arc::default(),
+            out: Arc::new(Mutex::new(out)),
+        })
+    }
+
+    fn send(&self, response: Response) {
+        send(&self.out, response);
+    }
+
+    fn wait_for_search(&mut self) {
+        if let Some(search) = self.search.take() {
+            self.engine = Some(search.waiter.join().expect("Search panics are caught inside the thread"));
+        }
+    }
+
+    fn stop(&mut self) {
+        if let Some(search) = &self.search {
+            search.control.stop();
+        }
+        self.wait_for_search();
+    }
+
+    fn set_position(&mut self, board: Board, plies: Vec<Ply>) -> Result<(), String> {
+        let mut game = GameState::new(board);
+        for ply in plies {
+            game.play(ply, PlyInfo::default()).map_err(|x| format!("Can't play {}: {}", ply, x))?;
+        }
+        self.game = game;
+        Ok(())
+    }
+
+    fn go(&mut self, mut limits: SearchLimits) {
+        let board = *self.game.get_board();
+        let turn = board.get_turn();
+
+        if board.get_victory().is_some() {
+            self.send(Response::InfoString(String::from("The game is over")));
+            self.send(Response::BestMove(None));
+            return;
+        }
+
+        let bounded = limits.time[turn].is_some()
+            || limits.movetime.is_some()
+            || limits.depth.is_some()
+            || limits.nodes.is_some()
+            || limits.mate.is_some()
+            || limits.infinite;
+        if !bounded {
+            limits.time[turn] = Some(DEFAULT_TIME);
+        }
+
+        // Every finished iteration is reported while the search runs
+        let out = self.out.clone();
+        let sink = InfoSink::new(move |x| send(&out, Response::Info(x.into())));
+
+        let engine = self.engine.take().expect("Searches are waited for before starting another");
+        let answered = Arc::new(AtomicBool::new(false));
+        let (control, waiter) = if board.in_setup() {
+            let handle = SearchHandle::place(engine, board, limits, Some(sink));
+            (handle.get_control(), self.answer(handle, limits.infinite, answered.clone(), placement_responses))
+        } else {
+            let handle = SearchHandle::search(engine, board, limits, Some(sink));
+            (handle.get_control(), self.answer(handle, limits.infinite, answered.clone(), search_responses))
+        };
+
+        self.search = Some(RunningSearch {
+            control,
+            infinite: limits.infinite,
+            answered,
+            waiter,
+        });
+    }
+
+    // Writes what the search found once it answers, which for an infinite search is only after
+    // stop even if the engine finished before. A crashed engine is replaced by a new one.
+    fn answer<T: Send + 'static>(&self, handle: SearchHandle<T>, infinite: bool, answered: Arc<AtomicBool>, responses: fn(T) -> Vec<Response>) -> JoinHandle<Box<dyn Engine>> {
+        let name = self.name.clone();
+        let options = self.options.clone();
+        let out = self.out.clone();
+        let control = handle.get_control();
+        thread::spawn(move || {
+            let answer = handle.wait();
+            while infinite && !control.is_stopped() {
+                thread::sleep(STOP_POLL);
+            }
+            answered.store(true, Ordering::Relaxed);
+            match answer {
+                Ok((engine, result)) => {
+                    for response in responses(result) {
+                        send(&out, response);
+                    }
+                    engine
+                }
+                Err(message) => {
+                    send(&out, Response::Error(format!("Engine crashed: {}", message)));
+                    new_engine(&name, &options)
+                }
+            }
+        })
+    }
+
+    // Returns false once the client asked to quit
+    pub fn handle(&mut self, line: &str) -> bool {
+        if line.trim().is_empty() {
+            return true;
+        }
+        let command = match line.parse::<Command>() {
+            Ok(command) => command,
+            Err(message) => {
+                self.send(Response::InfoString(message));
+                return true;
+            }
+        };
+
+        match command {
+            Command::IsReady => self.send(Response::ReadyOk),
+            Command::Quit => return false,
+            Command::Stop => self.stop(),
+            // Waiting for the search here would keep stop from being read
+            _ if self.search.as_ref().is_some_and(|x| !x.answered.load(Ordering::Relaxed)) => {
+                self.send(Response::InfoString(String::from("The engine is searching, send stop first")));
+            }
+            command => {
+                self.wait_for_search();
+                match command {
+                    Command::Sei => {
+                        let engine = self.engine.as_ref().unwrap();
+                        let EngineInfo { name, eval_range } = engine.get_info();
+                        self.send(Response::IdName(name));
+                        self.send(Response::IdEvalRange(eval_range.0, eval_range.1));
+                        for option in engine.get_options() {
+                            self.send(Response::Option(option));
+                        }
+                        self.send(Response::SeiOk);
+                    }
+                    Command::NewGame => {
+                        self.engine = Some(new_engine(&self.name, &self.options));
+                        self.game = GameState::new(Board::empty());
+                    }
+                    Command::SetOption { name, value } => match self.engine.as_mut().unwrap().set_option(&name, &value) {
+                        Ok(()) => Arc::make_mut(&mut self.options).push((name, value)),
+                        Err(message) => self.send(Response::InfoString(message)),
+                    },
+                    Command::Position { board, plies } => {
+                        if let Err(message) = self.set_position(board, plies) {
+                            self.send(Response::InfoString(message));
+                        }
+                    }
+                    Command::Go(limits) => self.go(limits),
+                    Command::IsReady | Command::Quit | Command::Stop => unreachable!(),
+                }
+            }
+        }
+
+        true
+    }
+
+    // Lets a running search finish so its bestmove is written. Infinite searches are stopped
+    // since nobody is left to stop them.
+    pub fn finish(&mut self) {
+        if self.search.as_ref().is_some_and(|x| x.infinite) {
+            self.stop();
+        }
+        self.wait_for_search();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Clone, Default)]
+    struct Output(Arc<Mutex<Vec<u8>>>);
+    impl Write for Output {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().write(buf)
+        }
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+    impl Output {
+        fn responses(&self) -> Vec<Response> {
+            let text = String::from_utf8(self.0.lock().unwrap().clone()).unwrap();
+            text.lines().map(|x| x.parse().unwrap()).collect()
+        }
+    }
+
+    fn run(commands: &[&str]) -> Vec<Response> {
+        let output = Output::default();
+        let mut server = Server::new("spectre", output.clone()).unwrap();
+        for command in commands {
+            assert!(server.handle(command));
+        }
+        server.finish();
+        output.responses()
+    }
+
+    #[test]
+    fn handshake() {
+        let responses = run(&["sei", "isready"]);
+        assert_eq!(responses, vec![
+            Response::IdName(String::from("spectre")),
+            Response::IdEvalRange(-46, 46),
+            Response::Option(String::from("Hash")),
+            Response::SeiOk,
+            Response::ReadyOk,
+        ]);
+    }
+
+    #[test]
+    fn bestmove() {
+        let responses = run(&[
+            "position 00000/00000/00000/00000/00000 - - P1 moves C3,B3 C2,D4",
+            "go p1time 3000 p2time 3000",
+        ]);
+        let Some(Response::BestMove(Some(Ply::Move(mv)))) = responses.last() else {
+            panic!("Expected a best move, got {:?}", responses);
+        };
+
+        let mut board = Board::empty();
+        board.place_workers([C3, B3]);
+        board.place_workers([C2, D4]);
+        assert!(board.is_legal(mv));
+        assert!(matches!(responses[0], Response::Info(Info { depth: Some(_), .. })));
+    }
+
+    #[test]
+    fn depth_limit() {
+        let responses = run(&["position 00000/00000/00000/00000/00000 B3,C2 C4,D3 P1", "go depth 2"]);
+        let summary = &responses[responses.len() - 2];
+        assert!(matches!(summary, Response::Info(Info { depth: Some(2), .. })));
+    }
+
+    #[test]
+    fn iterations_are_reported() {
+        let responses = run(&["position 00000/00000/00000/00000/00000 B3,C2 C4,D3 P1", "go depth 3"]);
+        let depths: Vec<usize> = responses
+            .iter()
+            .filter_map(|x| match x {
+                Response::Info(Info { depth: Some(depth), nodes: Some(_), pv: Some(_), .. }) => Some(*depth),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(depths, vec![1, 2, 3]);
+    }
+
+    #[test]
+    fn multi_pv() {
+        let responses = run(&["position 00000/00000/00000/00000/00000 B3,C2 C4,D3 P1", "go depth 2 multipv 3"]);
+        let lines: Vec<(usize, Score)> = responses
+            .iter()
+            .filter_map(|x| match x {
+                Response::Info(Info { multipv: Some(multipv), score: Some(score), .. }) => Some((*multipv, *score)),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(lines.iter().map(|x| x.0).collect::<Vec<usize>>(), vec![1, 2, 3]);
+        assert!(lines.windows(2).all(|x| x[0].1 >= x[1].1));
+    }
+
+    #[test]
+    fn placement() {
+        let responses = run(&["position 00000/00000/00000/00000/00000 - - P1", "go movetime 1000"]);
+        assert!(matches!(responses.last(), Some(Response::BestMove(Some(Ply::Place(_))))));
+    }
+
+    #[test]
+    fn errors_are_reported() {
+        let responses = run(&[
+            "dance",
+            "setoption name Contempt value 16",
+            "position 00000/00000/00000/00000/00000 B3,C2 C4,D3 P1 moves B3-C4^B3",
+            "isready",
+        ]);
+        assert_eq!(responses.len(), 4);
+        assert!(responses[..3].iter().all(|x| matches!(x, Response::InfoString(_))));
+        assert_eq!(responses[3], Response::ReadyOk);
+    }
+
+    #[test]
+    fn options() {
+        let responses = run(&["setoption name Hash value 1", "newgame", "setoption name Hash value none", "isready"]);
+        assert_eq!(responses.len(), 2);
+        assert!(matches!(responses[0], Response::InfoString(_)));
+
+        let responses = run(&["position 00000/00000/00000/00000/00000 B3,C2 C4,D3 P1", "go depth 3"]);
+        assert!(responses.iter().any(|x| matches!(x, Response::Info(Info { hashfull: Some(_), .. }))));
+    }
+
+    #[test]
+    fn game_over() {
+        let responses = run(&["position 00000/00000/00300/00000/00000 C3,A1 E4,E5 P2", "go"]);
+        assert_eq!(responses.last(), Some(&Response::BestMove(None)));
+    }
+
+    #[test]
+    fn stop() {
+        let output = Output::default();
+        let mut server = Server::new("spectre", output.clone()).unwrap();
+        assert!(server.handle("position 00000/00000/00000/00000/00000 B3,C2 C4,D3 P1"));
+        assert!(server.handle("go infinite"));
+        std::thread::sleep(Duration::from_millis(100));
+        assert!(server.handle("isready"));
+        assert!(server.handle("stop"));
+
+        // Iterations may be reported before readyok, but the best move only comes after it
+        let responses = output.responses();
+        let ready = responses.iter().position(|x| *x == Response::ReadyOk).expect("isready is answered during the search");
+        assert!(responses[..ready].iter().all(|x| matches!(x, Response::Info(_))));
+        assert!(matches!(responses.last(), Some(Response::BestMove(Some(Ply::Move(_))))));
+    }
+
+    #[test]
+    fn commands_during_search() {
+        let output = Output::default();
+        let mut server = Server::new("spectre", output.clone()).unwrap();
+        assert!(server.handle("position 00000/00000/00000/00000/00000 B3,C2 C4,D3 P1"));
+        assert!(server.handle("go infinite"));
+        assert!(server.handle("position 00000/00000/00000/00000/00000 B3,C2 C4,D3 P2"));
+        assert!(server.handle("go depth 1"));
+        assert!(server.handle("stop"));
+
+        let responses = output.responses();
+        assert_eq!(responses.iter().filter(|x| matches!(x, Response::InfoString(_))).count(), 2);
+        assert_eq!(responses.iter().filter(|x| matches!(x, Response::BestMove(_))).count(), 1);
+        let Some(Response::BestMove(Some(Ply::Move(mv)))) = responses.last() else {
+            panic!("Expected a best move, got {:?}", responses);
+        };
+        assert!(Board::new([B3, C2], [C4, D3]).is_legal(mv));
+    }
+
+    #[test]
+    fn infinite_waits_for_stop() {
+        let output = Output::default();
+        let mut server = Server::new("spectre", output.clone()).unwrap();
+        assert!(server.handle("position 00000/00000/00000/00000/00000 B3,C2 C4,D3 P1"));
+        assert!(server.handle("go infinite nodes 1000"));
+        std::thread::sleep(Duration::from_millis(300));
+        assert!(!output.responses().iter().any(|x| matches!(x, Response::BestMove(_))));
+
+        assert!(server.handle("stop"));
+        assert!(matches!(output.responses().last(), Some(Response::BestMove(Some(_)))));
+    }
+
+    #[test]
+    fn quit() {
+        let mut server = Server::new("flop", Output::default()).unwrap();
+        assert!(server.handle("isready"));
+        assert!(!server.handle("quit"));
+    }
+}
@@ -0,0 +1,68 @@
+// A set of squares, bit i standing for square i
+pub type Bitboard = u32;
+
+// Every square of the board
+pub const ALL: Bitboard = (1 << 25) - 1;
+// Two workers with at most eight squares to go to and eight to build on from there
+pub const MAX_MOVES: usize = 2 * 8 * 8;
+
+const fn make_neighbours() -> [Bitboard ; 25] {
+    let mut neighbours = [0 ; 25];
+    let mut square: usize = 0;
+    while square < 25 {
+        let (file, rank) = (square / 5, square % 5);
+        let mut other: usize = 0;
+        while other < 25 {
+            let (other_file, other_rank) = (other / 5, other % 5);
+            if other != square && file.abs_diff(other_file) <= 1 && rank.abs_diff(other_rank) <= 1 {
+                neighbours[square] |= 1 << other;
+            }
+            other += 1;
+        }
+        square += 1;
+    }
+    neighbours
+}
+
+// The squares next to each square, diagonals included
+pub const NEIGHBOURS: [Bitboard ; 25] = make_neighbours();
+
+pub fn get_neighbour_count(square: usize) -> usize {
+    NEIGHBOURS[square].count_ones() as usize
+}
+
+pub fn contains(bitboard: Bitboard, square: usize) -> bool {
+    bitboard & 1 << square != 0
+}
+
+// The squares of a bitboard in increasing order
+pub struct Squares(Bitboard);
+impl Iterator for Squares {
+    type Item = usize;
+
+    fn next(&mut self) -> Option<usize> {
+        if self.0 == 0 {
+            return None;
+        }
+        let square = self.0.trailing_zeros() as usize;
+        self.0 &= self.0 - 1;
+        Some(square)
+    }
+}
+pub fn squares(bitboard: Bitboard) -> Squares {
+    Squares(bitboard)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::helpers::squares::*;
+
+    #[test]
+    fn neighbours() {
+        assert_eq!(squares(NEIGHBOURS[A1]).collect::<Vec<usize>>(), vec![A2, B1, B2]);
+        assert_eq!(squares(NEIGHBOURS[C3]).collect::<Vec<usize>>(), vec![B2, B3, B4, C2, C4, D2, D3, D4]);
+        assert_eq!(squares(NEIGHBOURS[E3]).collect::<Vec<usize>>(), vec![D2, D3, D4, E2, E4]);
+        assert_eq!((0..25).map(get_neighbour_count).sum::<usize>(), 144);
+    }
+}
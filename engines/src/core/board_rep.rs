@@ -0,0 +1,743 @@
+use std::ops::{Deref, DerefMut};
+
+use crate::core::bitboard::*;
+#[cfg(test)]
+use crate::helpers::squares::*;
+use crate::helpers::turn::*;
+use crate::helpers::workers::*;
+
+#[cfg(test)]
+#[derive(Clone, Copy, Debug)]
+pub struct HalfMove {
+    pub from: usize,
+    pub to: usize,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Move {
+    pub from: usize,
+    pub to: usize,
+    pub build: usize,
+}
+
+// The moves of a position, kept on the stack so generating them doesn't allocate
+#[derive(Clone, Copy)]
+pub struct MoveList {
+    moves: [Move; MAX_MOVES],
+    len: usize,
+}
+impl MoveList {
+    pub fn new() -> Self {
+        MoveList {
+            moves: [Move {
+                from: 0,
+                to: 0,
+                build: 0,
+            }; MAX_MOVES],
+            len: 0,
+        }
+    }
+
+    fn push(&mut self, mv: Move) {
+        self.moves[self.len] = mv;
+        self.len += 1;
+    }
+}
+impl Default for MoveList {
+    fn default() -> Self {
+        MoveList::new()
+    }
+}
+impl Deref for MoveList {
+    type Target = [Move];
+
+    fn deref(&self) -> &[Move] {
+        &self.moves[..self.len]
+    }
+}
+impl DerefMut for MoveList {
+    fn deref_mut(&mut self) -> &mut [Move] {
+        &mut self.moves[..self.len]
+    }
+}
+
+// The heights and workers are kept both square by square and as bitboards, the first for the
+// eval and the second to generate moves
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct Board {
+    pub blocks: [u8; 25],
+    pub workers: [usize; 4],
+    pub turn: u8,
+    // Zobrist key of the position, kept up to date by make_move and undo_move
+    hash: u64,
+    // The squares exactly h blocks high, 4 being a dome
+    levels: [Bitboard; 5],
+    // The squares of W's workers, then U's
+    occupancy: [Bitboard; 2],
+}
+
+// Random keys xored together into the hash of a position: one per height of every square, one
+// per square for each colour's workers and one for U to move. Workers of the same colour share
+// their keys so swapping them gives the same hash.
+struct ZobristKeys {
+    blocks: [[u64; 5]; 25],
+    workers: [[u64; 25]; 2],
+    turn: u64,
+}
+
+// splitmix64, so the keys can be made at compile time and are the same on every run
+const fn next_key(state: &mut u64) -> u64 {
+    *state = state.wrapping_add(0x9e3779b97f4a7c15);
+    let mut z = *state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+    z ^ (z >> 31)
+}
+
+const fn make_keys() -> ZobristKeys {
+    let mut state = 0;
+    let mut keys = ZobristKeys {
+        blocks: [[0; 5]; 25],
+        workers: [[0; 25]; 2],
+        turn: 0,
+    };
+    let mut square = 0;
+    while square < 25 {
+        let mut height = 0;
+        while height < 5 {
+            keys.blocks[square][height] = next_key(&mut state);
+            height += 1;
+        }
+        keys.workers[0][square] = next_key(&mut state);
+        keys.workers[1][square] = next_key(&mut state);
+        square += 1;
+    }
+    keys.turn = next_key(&mut state);
+    keys
+}
+
+const ZOBRIST: ZobristKeys = make_keys();
+
+// Workers 0 and 1 belong to W, 2 and 3 to U
+const fn get_worker_key(worker: usize, square: usize) -> u64 {
+    ZOBRIST.workers[worker / 2][square]
+}
+
+#[cfg(test)]
+#[derive(Debug, PartialEq)]
+enum MoveError {
+    InvalidToSquare,
+    InvalidBuildSquare,
+    OccupiedToSquare,
+    OccupiedBuildSquare,
+    HeightDifferenceHigh,
+    ToSquareInaccessible,
+    BuildSquareInaccessible,
+    WorkerOfWrongColor,
+    InvalidBuildOnWin,
+    WorkerNotFound,
+}
+
+impl Board {
+    pub const fn new(blocks: [u8; 25], workers: [usize; 4], turn: u8) -> Self {
+        let mut hash = 0;
+        let mut square = 0;
+        while square < 25 {
+            hash ^= ZOBRIST.blocks[square][blocks[square] as usize];
+            square += 1;
+        }
+        let mut worker = 0;
+        while worker < 4 {
+            hash ^= get_worker_key(worker, workers[worker]);
+            worker += 1;
+        }
+        if turn == U {
+            hash ^= ZOBRIST.turn;
+        }
+
+        let mut levels = [0; 5];
+        let mut square = 0;
+        while square < 25 {
+            levels[blocks[square] as usize] |= 1 << square;
+            square += 1;
+        }
+        let occupancy = [
+            1 << workers[W1] | 1 << workers[W2],
+            1 << workers[U1] | 1 << workers[U2],
+        ];
+
+        Board {
+            blocks,
+            workers,
+            turn,
+            hash,
+            levels,
+            occupancy,
+        }
+    }
+
+    // Zobrist key of the position, what the transposition table and the searches key on
+    pub fn get_hash(&self) -> u64 {
+        self.hash
+    }
+
+    // 1 when W is to move and -1 when U is, to turn the eval into the side to move's view
+    pub fn get_color(&self) -> isize {
+        match self.turn {
+            W => 1,
+            U => -1,
+            _ => unreachable!(),
+        }
+    }
+
+    // Whether the side that just moved stands on the third level
+    pub fn game_is_over(&self) -> bool {
+        self.occupancy[1 - self.turn as usize] & self.levels[3] != 0
+    }
+
+    // Moves the bits of a worker and a build square, both ways
+    fn toggle(&mut self, worker: usize, mv: Move, height: usize, built: usize) {
+        self.occupancy[worker / 2] ^= 1 << mv.from | 1 << mv.to;
+        self.levels[height] ^= 1 << mv.build;
+        self.levels[built] ^= 1 << mv.build;
+    }
+
+    pub fn make_move(&mut self, mv: Move) {
+        let worker_to_move = match self.workers.iter().position(|&x| x == mv.from) {
+            Some(index) => index,
+            None => panic!("Worker not found"),
+        };
+        let height = self.blocks[mv.build] as usize;
+        self.hash ^=
+            get_worker_key(worker_to_move, mv.from) ^ get_worker_key(worker_to_move, mv.to);
+        self.hash ^= ZOBRIST.blocks[mv.build][height] ^ ZOBRIST.blocks[mv.build][height + 1];
+        self.hash ^= ZOBRIST.turn;
+        self.toggle(worker_to_move, mv, height, height + 1);
+        self.workers[worker_to_move] = mv.to;
+        self.blocks[mv.build] += 1;
+        self.turn = 1 - self.turn;
+    }
+
+    pub fn undo_move(&mut self, mv: Move) {
+        let worker_to_move = match self.workers.iter().position(|&x| x == mv.to) {
+            Some(index) => index,
+            None => panic!("Worker not found"),
+        };
+        let height = self.blocks[mv.build] as usize;
+        self.hash ^=
+            get_worker_key(worker_to_move, mv.to) ^ get_worker_key(worker_to_move, mv.from);
+        self.hash ^= ZOBRIST.blocks[mv.build][height] ^ ZOBRIST.blocks[mv.build][height - 1];
+        self.hash ^= ZOBRIST.turn;
+        self.toggle(worker_to_move, mv, height, height - 1);
+        self.workers[worker_to_move] = mv.from;
+        self.blocks[mv.build] -= 1;
+        self.turn = 1 - self.turn;
+    }
+
+    // Fills moves with every legal move, by worker, then by the squares moved to and built on.
+    // A move onto the third level wins and has the worker's old square as its build square.
+    pub fn generate_moves(&self, moves: &mut MoveList) {
+        moves.len = 0;
+        let free = !(self.occupancy[0] | self.occupancy[1] | self.levels[4]) & ALL;
+        let first = 2 * self.turn as usize;
+        for &from in &self.workers[first..first + 2] {
+            let reachable = self.levels[..=self.blocks[from] as usize + 1]
+                .iter()
+                .fold(0, |x, y| x | y);
+            for to in squares(NEIGHBOURS[from] & free & reachable) {
+                if contains(self.levels[3], to) {
+                    moves.push(Move {
+                        from,
+                        to,
+                        build: from,
+                    });
+                    continue;
+                }
+                for build in squares(NEIGHBOURS[to] & (free | 1 << from)) {
+                    moves.push(Move { from, to, build });
+                }
+            }
+        }
+    }
+}
+
+// The rules checked one move at a time, which the generator is tested against
+#[cfg(test)]
+impl Board {
+    fn square_is_free(&self, square: usize) -> bool {
+        self.workers[W1] != square
+            && self.workers[W2] != square
+            && self.workers[U1] != square
+            && self.workers[U2] != square
+            && self.blocks[square] < 4
+    }
+
+    fn half_move_is_legal(&self, hm: HalfMove) -> Result<(), MoveError> {
+        if hm.from != self.workers[W1]
+            && hm.from != self.workers[W2]
+            && hm.from != self.workers[U1]
+            && hm.from != self.workers[U2]
+        {
+            return Err(MoveError::WorkerNotFound);
+        }
+
+        if hm.to > E5 {
+            return Err(MoveError::InvalidToSquare);
+        }
+
+        if !self.square_is_free(hm.to) {
+            return Err(MoveError::OccupiedToSquare);
+        }
+
+        if self.blocks[hm.to] > self.blocks[hm.from] + 1 {
+            return Err(MoveError::HeightDifferenceHigh);
+        }
+
+        if (self.turn == W && (hm.from == self.workers[U1] || hm.from == self.workers[U2]))
+            || (self.turn == U && (hm.from == self.workers[W1] || hm.from == self.workers[W2]))
+        {
+            return Err(MoveError::WorkerOfWrongColor);
+        }
+        if !contains(NEIGHBOURS[hm.to], hm.from) {
+            return Err(MoveError::ToSquareInaccessible);
+        }
+        Ok(())
+    }
+
+    fn move_is_legal(&self, mv: Move) -> Result<(), MoveError> {
+        let half_move = HalfMove {
+            from: mv.from,
+            to: mv.to,
+        };
+
+        self.half_move_is_legal(half_move)?;
+
+        if mv.build > E5 {
+            return Err(MoveError::InvalidBuildSquare);
+        }
+
+        if (!self.square_is_free(mv.build) && mv.build != mv.from) || (mv.build == mv.to) {
+            return Err(MoveError::OccupiedBuildSquare);
+        }
+
+        if !contains(NEIGHBOURS[mv.build], mv.to) {
+            return Err(MoveError::BuildSquareInaccessible);
+        }
+
+        if self.blocks[mv.to] == 3 && mv.build != mv.from {
+            return Err(MoveError::InvalidBuildOnWin);
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn moves_of(board: &Board) -> MoveList {
+        let mut moves = MoveList::new();
+        board.generate_moves(&mut moves);
+        moves
+    }
+
+    const TEST_BOARD_1: Board = Board::new(
+        [
+            0, 0, 0, 0, 0, 0, 1, 0, 0, 0, 0, 4, 0, 2, 0, 0, 0, 0, 0, 0, 0, 0, 1, 0, 2,
+        ],
+        [C4, D4, B3, C3],
+        W,
+    );
+    const TEST_BOARD_2: Board = Board::new(
+        [
+            0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+        ],
+        [A1, E5, A5, E1],
+        U,
+    );
+    const TEST_BOARD_3: Board = Board::new(
+        [
+            0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 2, 3, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+        ],
+        [C4, D4, B3, C3],
+        W,
+    );
+    #[test]
+    fn worker_not_found() {
+        let board = TEST_BOARD_1;
+
+        let mv = Move {
+            from: E5,
+            to: D5,
+            build: D1,
+        };
+        assert_eq!(board.move_is_legal(mv), Err(MoveError::WorkerNotFound));
+    }
+    #[test]
+    fn invalid_to_square() {
+        let board = TEST_BOARD_1;
+
+        let mv = Move {
+            from: D4,
+            to: 25,
+            build: D4,
+        };
+        assert_eq!(board.move_is_legal(mv), Err(MoveError::InvalidToSquare));
+    }
+    #[test]
+    fn invalid_build_square() {
+        let board = TEST_BOARD_1;
+
+        let mv = Move {
+            from: D4,
+            to: D5,
+            build: 25,
+        };
+        assert_eq!(board.move_is_legal(mv), Err(MoveError::InvalidBuildSquare));
+    }
+    #[test]
+    fn to_square_far_away() {
+        let board = TEST_BOARD_1;
+
+        let mv = Move {
+            from: D4,
+            to: A1,
+            build: A2,
+        };
+        assert_eq!(
+            board.move_is_legal(mv),
+            Err(MoveError::ToSquareInaccessible)
+        );
+    }
+    #[test]
+    fn build_square_far_away() {
+        let board = TEST_BOARD_1;
+
+        let mv = Move {
+            from: D4,
+            to: D5,
+            build: A2,
+        };
+        assert_eq!(
+            board.move_is_legal(mv),
+            Err(MoveError::BuildSquareInaccessible)
+        );
+    }
+    #[test]
+    fn to_square_occupied() {
+        let board = TEST_BOARD_1;
+
+        let mv = Move {
+            from: D4,
+            to: C3,
+            build: D3,
+        };
+        assert_eq!(board.move_is_legal(mv), Err(MoveError::OccupiedToSquare));
+    }
+    #[test]
+    fn build_square_occupied() {
+        let board = TEST_BOARD_1;
+
+        let mv = Move {
+            from: D4,
+            to: D3,
+            build: C3,
+        };
+        assert_eq!(board.move_is_legal(mv), Err(MoveError::OccupiedBuildSquare));
+    }
+    #[test]
+    fn move_to_self() {
+        let board = TEST_BOARD_1;
+
+        let mv = Move {
+            from: D4,
+            to: D4,
+            build: D3,
+        };
+        assert_eq!(board.move_is_legal(mv), Err(MoveError::OccupiedToSquare));
+    }
+    #[test]
+    fn build_in_previous_square() {
+        let board = TEST_BOARD_1;
+
+        let mv = Move {
+            from: D4,
+            to: D5,
+            build: D4,
+        };
+        assert_eq!(board.move_is_legal(mv), Ok(()));
+    }
+    #[test]
+    fn build_in_new_square() {
+        let board = TEST_BOARD_1;
+
+        let mv = Move {
+            from: D4,
+            to: D5,
+            build: D5,
+        };
+        assert_eq!(board.move_is_legal(mv), Err(MoveError::OccupiedBuildSquare));
+    }
+    #[test]
+    fn height_difference() {
+        let board = TEST_BOARD_1;
+
+        let mv = Move {
+            from: D4,
+            to: E5,
+            build: E4,
+        };
+        assert_eq!(
+            board.move_is_legal(mv),
+            Err(MoveError::HeightDifferenceHigh)
+        );
+    }
+    #[test]
+    fn wrong_color() {
+        let board = TEST_BOARD_1;
+
+        let mv = Move {
+            from: B3,
+            to: A3,
+            build: A2,
+        };
+        assert_eq!(board.move_is_legal(mv), Err(MoveError::WorkerOfWrongColor));
+    }
+    #[test]
+    fn wrong_color_2() {
+        let board = TEST_BOARD_2;
+
+        let mv = Move {
+            from: A1,
+            to: A2,
+            build: A3,
+        };
+        assert_eq!(board.move_is_legal(mv), Err(MoveError::WorkerOfWrongColor));
+    }
+    #[test]
+    fn wrong_build_on_win() {
+        let board = TEST_BOARD_3;
+        let mv = Move {
+            from: C4,
+            to: C5,
+            build: D5,
+        };
+        assert_eq!(board.move_is_legal(mv), Err(MoveError::InvalidBuildOnWin));
+    }
+    #[test]
+    fn normal_move() {
+        let board = TEST_BOARD_1;
+
+        let mv = Move {
+            from: D4,
+            to: D5,
+            build: E4,
+        };
+        assert_eq!(board.move_is_legal(mv), Ok(()));
+    }
+    #[test]
+    fn diagonal_move() {
+        let board = TEST_BOARD_1;
+
+        let mv = Move {
+            from: D4,
+            to: C5,
+            build: B5,
+        };
+        assert_eq!(board.move_is_legal(mv), Ok(()));
+    }
+    #[test]
+    fn climbing_move() {
+        let board = TEST_BOARD_1;
+
+        let mv = Move {
+            from: D4,
+            to: E3,
+            build: E2,
+        };
+        assert_eq!(board.move_is_legal(mv), Ok(()));
+    }
+    #[test]
+    fn jumping_move() {
+        let board = TEST_BOARD_1;
+
+        let mv = Move {
+            from: C4,
+            to: C5,
+            build: B5,
+        };
+        assert_eq!(board.move_is_legal(mv), Ok(()));
+    }
+
+    #[test]
+    fn opening_position() {
+        let board = Board::new(
+            [
+                0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+            ],
+            [B3, C3, C2, C4],
+            W,
+        );
+        let len_moves = moves_of(&board).len();
+        assert_eq!(len_moves, 59);
+    }
+    #[test]
+    fn trapped_worker() {
+        let board = Board::new(
+            [
+                0, 0, 0, 2, 0, 0, 0, 0, 0, 3, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+            ],
+            [A5, C3, B3, B4],
+            W,
+        );
+        let len_moves = moves_of(&board).len();
+        assert_eq!(len_moves, 44);
+    }
+    #[test]
+    fn winning_move() {
+        let board = Board::new(
+            [
+                0, 0, 0, 0, 0, 0, 3, 0, 0, 0, 0, 2, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+            ],
+            [C2, C3, B3, B4],
+            W,
+        );
+        let len_moves = moves_of(&board).len();
+        assert_eq!(len_moves, 58);
+    }
+    #[test]
+    fn domed() {
+        let board = Board::new(
+            [
+                0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 4, 4, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+            ],
+            [A1, E1, D2, E2],
+            W,
+        );
+        let len_moves = moves_of(&board).len();
+        assert_eq!(len_moves, 15);
+    }
+    #[test]
+    fn multiple_heights() {
+        let board = Board::new(
+            [
+                0, 0, 0, 0, 0, 0, 0, 2, 0, 0, 0, 1, 2, 1, 0, 0, 0, 3, 0, 0, 0, 0, 0, 0, 0,
+            ],
+            [C2, C4, B3, A3],
+            W,
+        );
+        let len_moves = moves_of(&board).len();
+        assert_eq!(len_moves, 70);
+    }
+    #[test]
+    fn zero_moves() {
+        let board = Board::new(
+            [
+                1, 0, 0, 0, 0, 1, 3, 0, 0, 0, 4, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+            ],
+            [A1, B1, A2, C2],
+            W,
+        );
+        let len_moves = moves_of(&board).len();
+        assert_eq!(len_moves, 0);
+    }
+    #[test]
+    fn make_move() {
+        let mut board = Board::new(
+            [
+                0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+            ],
+            [C3, C4, B3, D3],
+            W,
+        );
+        let mv = Move {
+            from: C3,
+            to: C2,
+            build: C1,
+        };
+        board.make_move(mv);
+        let board_2 = Board::new(
+            [
+                0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+            ],
+            [C2, C4, B3, D3],
+            U,
+        );
+        assert_eq!(board, board_2);
+    }
+    #[test]
+    fn undo_move() {
+        let mut board = Board::new(
+            [
+                0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+            ],
+            [C3, C4, B3, D3],
+            W,
+        );
+        let mv = Move {
+            from: C3,
+            to: C2,
+            build: C1,
+        };
+        board.make_move(mv);
+        board.undo_move(mv);
+        let board_2 = Board::new(
+            [
+                0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+            ],
+            [C3, C4, B3, D3],
+            W,
+        );
+        assert_eq!(board, board_2);
+    }
+    #[test]
+    fn zobrist() {
+        let mut board = TEST_BOARD_1;
+        let mut played = vec![];
+        for _ in 0..6 {
+            let mv = moves_of(&board)[0];
+            board.make_move(mv);
+            played.push(mv);
+            assert_eq!(
+                board.get_hash(),
+                Board::new(board.blocks, board.workers, board.turn).get_hash()
+            );
+            // The bitboards are kept up to date too
+            assert_eq!(board, Board::new(board.blocks, board.workers, board.turn));
+        }
+        for mv in played.into_iter().rev() {
+            board.undo_move(mv);
+        }
+        assert_eq!(board.get_hash(), TEST_BOARD_1.get_hash());
+
+        // The side to move and the order of a colour's workers
+        let other_turn = Board::new(TEST_BOARD_1.blocks, TEST_BOARD_1.workers, U);
+        assert_ne!(other_turn.get_hash(), TEST_BOARD_1.get_hash());
+        let swapped = Board::new(TEST_BOARD_1.blocks, [D4, C4, B3, C3], W);
+        assert_eq!(swapped.get_hash(), TEST_BOARD_1.get_hash());
+        let other_colour = Board::new(TEST_BOARD_1.blocks, [B3, C3, C4, D4], W);
+        assert_ne!(other_colour.get_hash(), TEST_BOARD_1.get_hash());
+    }
+    #[test]
+    fn generator_follows_rules() {
+        for board in [TEST_BOARD_1, TEST_BOARD_2, TEST_BOARD_3] {
+            let mut generated: Vec<(usize, usize, usize)> = moves_of(&board)
+                .iter()
+                .map(|x| (x.from, x.to, x.build))
+                .collect();
+            generated.sort();
+            let mut legal = vec![];
+            for from in A1..=E5 {
+                for to in A1..=E5 {
+                    for build in A1..=E5 {
+                        if board.move_is_legal(Move { from, to, build }).is_ok() {
+                            legal.push((from, to, build));
+                        }
+                    }
+                }
+            }
+            assert_eq!(generated, legal);
+        }
+    }
+}
@@ -0,0 +1,154 @@
+use crate::core::bitboard::get_neighbour_count;
+use crate::core::board_rep::*;
+use crate::helpers::workers::*;
+
+fn position_height (board: &Board, p:fn (&Board, usize) -> usize, a:usize, b:usize, c:usize, worker_pos:usize) -> usize {
+    let h = board.blocks[worker_pos];
+    a * b.pow(h.into()) + c * p(board, worker_pos)
+}
+
+fn num_neighbors(_:&Board, worker_pos:usize) -> usize{
+    get_neighbour_count(worker_pos)
+}
+
+fn neighbor_height(board:&Board, a:usize, b:usize, c:usize, worker_pos:usize) -> usize {
+    position_height(board, num_neighbors, a, b, c, worker_pos)
+}
+
+fn nh_s(board:&Board, worker_pos:usize) -> usize{
+    neighbor_height(board, 6, 2, 1, worker_pos)
+}
+
+pub fn eval (board: &Board) -> isize {
+    (nh_s(board, board.workers[W1]) + nh_s(board, board.workers[W2])) as isize -
+    (nh_s(board, board.workers[U1]) + nh_s(board, board.workers[U2])) as isize
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::helpers::squares::*;
+    use crate::helpers::turn::*;
+
+    #[test]
+    fn starting_position (){
+        let board = 
+            Board::new(
+                [0, 0, 0, 0, 0,
+                 0, 0, 0, 0, 0,
+                 0, 0, 0, 0, 0,
+                 0, 0, 0, 0, 0,
+                 0, 0, 0, 0, 0],
+                [C3, C4, B3, D3],
+                W,
+            );
+
+        assert_eq!(eval(&board), 0);
+    }
+    #[test]
+    fn simple_height (){
+        let board = 
+            Board::new(
+                [0, 0, 0, 0, 0,
+                 0, 1, 0, 0, 0,
+                 0, 0, 0, 0, 0,
+                 0, 0, 0, 2, 0,
+                 0, 0, 0, 0, 0],
+                [B2, B4, D2, D4],
+                W,
+            );
+
+        assert!(eval(&board) < 0);
+    }
+    #[test]
+    fn simple_pos (){
+        let board = 
+            Board::new(
+                [0, 0, 0, 0, 0,
+                 0, 0, 0, 0, 0,
+                 0, 0, 0, 0, 0,
+                 0, 0, 0, 0, 0,
+                 0, 0, 0, 0, 0],
+                [C2, C1, C4, A1],
+                W,
+            );
+
+        assert!(eval(&board) > 0);
+    }
+    #[test]
+    fn h2_over_h1 (){
+        let board = 
+            Board::new(
+                [2, 0, 0, 0, 0,
+                 0, 0, 0, 0, 0,
+                 0, 0, 1, 0, 0,
+                 0, 0, 0, 0, 0,
+                 0, 0, 0, 0, 0],
+                [A1, C4, C3, D4],
+                W,
+            );
+
+        assert!(eval(&board) > 0);
+    }
+    #[test]
+    fn h_not_all (){
+        let board = 
+            Board::new(
+                [2, 0, 0, 0, 0,
+                 0, 0, 0, 0, 0,
+                 0, 0, 0, 1, 0,
+                 0, 0, 0, 1, 0,
+                 0, 0, 0, 0, 0],
+                [A1, A5, C4, D4],
+                W,
+            );
+
+        assert!(eval(&board) < 0);
+    }
+    #[test]
+    fn border_over_corner (){
+        let board = 
+            Board::new(
+                [0, 0, 0, 0, 0,
+                 0, 0, 0, 0, 0,
+                 0, 0, 0, 0, 0,
+                 0, 0, 0, 0, 0,
+                 0, 0, 0, 0, 0],
+                [A1, C4, A2, D4],
+                W,
+            );
+
+        assert!(eval(&board) < 0);
+    }
+    #[test]
+    fn center_over_border (){
+        let board = 
+            Board::new(
+                [0, 0, 0, 0, 0,
+                 0, 0, 0, 0, 0,
+                 0, 0, 0, 0, 0,
+                 0, 0, 0, 0, 0,
+                 0, 0, 0, 0, 0],
+                [A2, C4, B2, D4],
+                W,
+            );
+
+        assert!(eval(&board) < 0);
+    }
+    #[test]
+    fn height_over_pos (){
+        let board = 
+            Board::new(
+                [1, 0, 0, 0, 0,
+                 0, 0, 0, 0, 0,
+                 0, 0, 0, 0, 0,
+                 0, 0, 0, 0, 0,
+                 0, 0, 0, 0, 0],
+                [A1, C4, A3, D4],
+                W,
+            );
+
+        assert!(eval(&board) > 0);
+    }
+}
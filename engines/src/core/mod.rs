@@ -0,0 +1,61 @@
+// What the engines share: the board with its move generation, the eval and time management.
+// Each engine only brings its own search on top.
+pub mod bitboard;
+pub mod board_rep;
+pub mod eval;
+pub mod search;
+pub mod time_management;
+
+use crate::models::*;
+
+pub fn convert_move(board: &board_rep::Board, internal_move: board_rep::Move) -> Move {
+    let at = if board.blocks[internal_move.to] == 3 {
+        None
+    } else {
+        Some(internal_move.build.try_into().unwrap())
+    };
+    Move {
+        from: internal_move.from.try_into().unwrap(),
+        to: internal_move.to.try_into().unwrap(),
+        at,
+    }
+}
+
+// Moves are converted one after the other on a copy of the board, since a winning move depends
+// on the heights once the moves before it are played
+pub fn convert_pv(board: &board_rep::Board, pv: &[board_rep::Move]) -> Vec<Move> {
+    let mut board = *board;
+    pv.iter()
+        .map(|&mv| {
+            let converted = convert_move(&board, mv);
+            board.make_move(mv);
+            converted
+        })
+        .collect()
+}
+
+pub fn convert_board(request: Request) -> board_rep::Board {
+    let mut workers = [0 ; 4];
+    let mut index1 = 0;
+    let mut index2 = 2;
+    for square in Square::squares() {
+        if let Some(Worker { turn }) = request.workers[square] {
+            match turn {
+                Turn::P1 => {
+                    workers[index1] = square.into();
+                    index1 += 1;
+                }
+                Turn::P2 => {
+                    workers[index2] = square.into();
+                    index2 += 1;
+                }
+            }
+        }
+    }
+
+    board_rep::Board::new(
+        request.blocks.map(|x| Into::<usize>::into(x) as u8),
+        workers,
+        Into::<usize>::into(*request.turn) as u8,
+    )
+}
@@ -0,0 +1,44 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::core::board_rep::Board;
+use crate::core::convert_board;
+use crate::core::time_management::get_thinking_time;
+use crate::models::{InfoSink, Request, SearchControl};
+
+// Nodes searched between two looks at the clock
+pub const CHECK_CLOCK_EVERY: usize = 1000;
+// We use a fixed depth to avoid growing to unnecessary depths when a game-ending move is found
+pub const MAX_DEPTH: usize = 20;
+
+// A request with the limits worked out, what every engine's search starts from
+#[derive(Debug)]
+pub struct SearchRequest{
+    pub position:Board,
+    pub max_depth:usize,
+    pub time_left:Option<Duration>,
+    pub max_nodes:Option<usize>,
+    // How many root moves to rank, at least one
+    pub multi_pv:usize,
+    pub control:Arc<SearchControl>,
+    pub sink:Option<InfoSink>,
+    pub debug: bool,
+}
+impl SearchRequest {
+    pub fn from_request(request: Request, debug: bool) -> Self {
+        let limits = request.limits;
+        let turn = *request.turn;
+        let control = request.control.clone();
+        let sink = request.sink.clone();
+        SearchRequest {
+            position: convert_board(request),
+            max_depth: limits.get_max_depth().unwrap_or(MAX_DEPTH),
+            time_left: get_thinking_time(&limits, turn),
+            max_nodes: limits.nodes,
+            multi_pv: limits.multi_pv.unwrap_or(1).max(1),
+            control,
+            sink,
+            debug,
+        }
+    }
+}
@@ -0,0 +1,93 @@
+use std::time::Duration;
+
+use crate::models::{SearchLimits, Turn};
+
+fn estimated_time_left(time_left:Duration, a:usize) -> Duration {
+    Duration::from_nanos((time_left.as_nanos() / (a as u128)).try_into().unwrap())
+}
+
+fn etl_s(time_left:Duration) -> Duration {
+    estimated_time_left(time_left, 15)
+}
+
+pub fn get_time(time_left:Duration) -> Duration{
+    etl_s(time_left)
+}
+
+// How long to think for, None when only the depth or node limits end the search
+pub fn get_thinking_time(limits: &SearchLimits, turn: Turn) -> Option<Duration> {
+    if limits.infinite {
+        return None;
+    }
+    // A tenth of a fixed movetime is kept back so the answer arrives in time
+    match (limits.movetime, limits.time[turn]) {
+        (Some(movetime), Some(time_left)) => Some(movetime.min(time_left) * 9 / 10),
+        (Some(movetime), None) => Some(movetime * 9 / 10),
+        (None, Some(time_left)) => Some(get_clock_time(limits, turn, time_left)),
+        (None, None) => None,
+    }
+}
+
+// Spreads the clock over the moves left in the period, at most 15 of them, and spends most of
+// the increment on top. A lead over the opponent's clock is spent a fifteenth at a time too.
+fn get_clock_time(limits: &SearchLimits, turn: Turn, time_left: Duration) -> Duration {
+    let mut thinking_time = match limits.moves_to_go {
+        // Some time is kept back on the last move of a period in case the program is slow to answer
+        Some(moves) => estimated_time_left(time_left, (moves + 1).min(15)),
+        None => get_time(time_left),
+    };
+    thinking_time += limits.increment[turn] * 3 / 4;
+    if let Some(opponent_time) = limits.time[turn.next()] {
+        thinking_time += get_time(time_left.saturating_sub(opponent_time));
+    }
+    // The increment only comes after the move, so half the clock is the most we risk
+    thinking_time.min(time_left / 2)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_1min(){
+        let dur = Duration::from_secs(60);
+        assert_eq!(get_time(dur), Duration::from_secs(4));
+    }
+
+    #[test]
+    fn test_3min(){
+        let dur = Duration::from_secs(180);
+        assert_eq!(get_time(dur), Duration::from_secs(12));
+    }
+
+    #[test]
+    fn test_15min(){
+        let dur = Duration::from_secs(900);
+        assert_eq!(get_time(dur), Duration::from_secs(60));
+    }
+
+    #[test]
+    fn limits(){
+        let limits = SearchLimits::clock(Turn::P2, Duration::from_secs(60));
+        assert_eq!(get_thinking_time(&limits, Turn::P2), Some(Duration::from_secs(4)));
+        assert_eq!(get_thinking_time(&limits, Turn::P1), None);
+        assert_eq!(get_thinking_time(&SearchLimits::movetime(Duration::from_secs(2)), Turn::P1), Some(Duration::from_millis(1800)));
+        assert_eq!(get_thinking_time(&SearchLimits { infinite: true, ..limits }, Turn::P2), None);
+    }
+
+    #[test]
+    fn clock_limits(){
+        let mut limits = SearchLimits::clock(Turn::P1, Duration::from_secs(60));
+        limits.increment = [Duration::from_secs(2) ; 2];
+        assert_eq!(get_thinking_time(&limits, Turn::P1), Some(Duration::from_millis(5500)));
+
+        limits.time[Turn::P2] = Some(Duration::from_secs(30));
+        assert_eq!(get_thinking_time(&limits, Turn::P1), Some(Duration::from_millis(7500)));
+
+        let limits = SearchLimits { moves_to_go: Some(3), ..SearchLimits::clock(Turn::P1, Duration::from_secs(60)) };
+        assert_eq!(get_thinking_time(&limits, Turn::P1), Some(Duration::from_secs(15)));
+
+        let limits = SearchLimits { moves_to_go: Some(1), ..SearchLimits::clock(Turn::P1, Duration::from_secs(60)) };
+        assert_eq!(get_thinking_time(&limits, Turn::P1), Some(Duration::from_secs(30)));
+    }
+}
@@ -0,0 +1,370 @@
+use std::{
+    io::{BufRead, BufReader, Write},
+    process::{Child, ChildStdin, Command as Process, Stdio},
+    sync::{mpsc::{self, Receiver, RecvTimeoutError}, Mutex},
+    thread,
+    time::{Duration, Instant},
+};
+
+use crate::models::*;
+use crate::protocol::*;
+
+// How long a program gets to answer anything that isn't a search
+const ANSWER_TIME: Duration = Duration::from_secs(5);
+// How often a search looks whether it was asked to stop
+const STOP_POLL: Duration = Duration::from_millis(10);
+
+// An engine running in its own process and speaking the text protocol of engines::protocol,
+// such as an older build deployed with engine-deploy or a third party engine.
+pub struct ExternalEngine {
+    info: EngineInfo,
+    // Advertised during the handshake
+    options: Vec<String>,
+    child: Child,
+    // Mutexes because Engine only lends &self while searching
+    stdin: Mutex<ChildStdin>,
+    lines: Mutex<Receiver<String>>,
+}
+impl ExternalEngine {
+    // Starts the program and waits for its handshake
+    pub fn spawn(program: &str, args: &[&str]) -> Result<Self, String> {
+        let mut child = Process::new(program)
+            .args(args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()
+            .map_err(|x| format!("Can't start {}: {}", program, x))?;
+
+        let stdin = child.stdin.take().unwrap();
+        let stdout = child.stdout.take().unwrap();
+
+        // Lines are read on their own thread so waiting for them can time out
+        let (sender, receiver) = mpsc::channel();
+        thread::spawn(move || {
+            for line in BufReader::new(stdout).lines() {
+                let Ok(line) = line else {
+                    break;
+                };
+                if sender.send(line).is_err() {
+                    break;
+                }
+            }
+        });
+
+        let mut engine = ExternalEngine {
+            info: EngineInfo {
+                name: program.to_string(),
+                eval_range: (0, 0),
+            },
+            options: vec![],
+            child,
+            stdin: Mutex::new(stdin),
+            lines: Mutex::new(receiver),
+        };
+
+        engine.send(Command::Sei)?;
+        let deadline = Instant::now() + ANSWER_TIME;
+        loop {
+            let response = engine.receive(deadline)?.ok_or(format!("{} didn't answer in time", program))?;
+            match response {
+                Response::IdName(name) => engine.info.name = name,
+                Response::IdEvalRange(min, max) => engine.info.eval_range = (min, max),
+                Response::Option(name) => engine.options.push(name),
+                Response::SeiOk => break,
+                _ => {}
+            }
+        }
+
+        Ok(engine)
+    }
+
+    fn send(&self, command: Command) -> Result<(), String> {
+        let mut stdin = self.stdin.lock().unwrap();
+        writeln!(stdin, "{}", command)
+            .and_then(|_| stdin.flush())
+            .map_err(|x| format!("Can't write to {}: {}", self.info.name, x))
+    }
+
+    // Waits for the next response the adapter understands, skipping anything else, or None
+    // once the deadline passes
+    fn receive(&self, deadline: Instant) -> Result<Option<Response>, String> {
+        let lines = self.lines.lock().unwrap();
+        loop {
+            match lines.recv_timeout(deadline.saturating_duration_since(Instant::now())) {
+                Ok(line) => {
+                    if let Ok(response) = line.parse() {
+                        return Ok(Some(response));
+                    }
+                }
+                Err(RecvTimeoutError::Timeout) => return Ok(None),
+                Err(RecvTimeoutError::Disconnected) => return Err(format!("{} exited", self.info.name)),
+            }
+        }
+    }
+
+    // Sends the position and waits for bestmove, keeping the last info line about the best move
+    // and passing every complete one on to the request's sink, along with the last round of
+    // multipv lines. A program that doesn't answer within its clock or movetime, or whose search
+    // was stopped through the request, is told to stop and given a little longer.
+    fn search(&self, request: Request) -> Result<(Option<Ply>, Info, Vec<RootMoveResult>), String> {
+        let board = request.get_board();
+        let limits = request.limits;
+        let time_left = match (request.get_time_left(), limits.movetime) {
+            (Some(time_left), Some(movetime)) => Some(time_left.min(movetime)),
+            (time_left, movetime) => time_left.or(movetime),
+        };
+
+        // Drop anything left over from an earlier search that answered too late
+        while self.lines.lock().unwrap().try_recv().is_ok() {}
+
+        self.send(Command::Position { board, plies: vec![] })?;
+        self.send(Command::Go(limits))?;
+
+        let stop_at = time_left.map(|x| Instant::now() + x);
+        let mut answer_by: Option<Instant> = None;
+        let mut info = Info::default();
+        let mut root_moves = vec![];
+        loop {
+            match self.receive(Instant::now() + STOP_POLL)? {
+                Some(Response::Info(latest)) => {
+                    // A new round of multipv lines starts with the best one
+                    if latest.multipv == Some(1) {
+                        root_moves.clear();
+                    }
+                    if latest.multipv.is_some() {
+                        root_moves.extend(to_root_move(&latest));
+                    }
+                    if latest.multipv.is_none_or(|x| x == 1) {
+                        if let (Some(sink), Some(search_info)) = (&request.sink, to_search_info(&latest)) {
+                            sink.send(&search_info);
+                        }
+                        info = latest;
+                    }
+                }
+                Some(Response::BestMove(ply)) => return Ok((ply, info, root_moves)),
+                Some(Response::Error(message)) => return Err(format!("{} failed: {}", self.info.name, message)),
+                Some(_) => {}
+                None => match answer_by {
+                    Some(answer_by) if Instant::now() > answer_by => {
+                        return Err(format!("{} didn't answer in time", self.info.name));
+                    }
+                    Some(_) => {}
+                    None => {
+                        if request.control.is_stopped() || stop_at.is_some_and(|x| Instant::now() > x) {
+                            self.send(Command::Stop)?;
+                            answer_by = Some(Instant::now() + ANSWER_TIME);
+                        }
+                    }
+                },
+            }
+        }
+    }
+}
+
+// Only info lines with at least a depth, score and time say enough to be passed on
+fn to_search_info(info: &Info) -> Option<SearchInfo> {
+    let elapsed = info.time?;
+    let nodes = info.nodes.unwrap_or(0);
+    Some(SearchInfo {
+        depth: info.depth?,
+        seldepth: info.seldepth.or(info.depth)?,
+        score: info.score?,
+        nodes,
+        nps: info.nps.unwrap_or(SearchInfo::get_nps(nodes, elapsed)),
+        elapsed,
+        pv: info.pv.clone().unwrap_or_default(),
+        hashfull: info.hashfull,
+    })
+}
+
+fn to_root_move(info: &Info) -> Option<RootMoveResult> {
+    let pv = info.pv.clone()?;
+    Some(RootMoveResult {
+        mv: *pv.first()?,
+        eval: info.score?,
+        pv,
+    })
+}
+
+impl Drop for ExternalEngine {
+    fn drop(&mut self) {
+        let _ = self.send(Command::Quit);
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+// Engine errors have no way back to the caller other than a panic, which game-manager turns
+// into a forfeit
+impl Engine for ExternalEngine {
+    fn get_info(&self) -> EngineInfo {
+        EngineInfo {
+            name: self.info.name.clone(),
+            eval_range: self.info.eval_range,
+        }
+    }
+
+    fn get_search_result(&self, request: Request) -> SearchResult {
+        let start = Instant::now();
+        let (ply, info, root_moves) = self.search(request).unwrap_or_else(|message| panic!("{}", message));
+        let Some(Ply::Move(mv)) = ply else {
+            panic!("{} answered a move request with {:?}", self.info.name, ply);
+        };
+
+        SearchResult {
+            mv,
+            eval: info.score,
+            pv: info.pv,
+            depth_searched: info.depth,
+            time_spent: Some(info.time.unwrap_or(start.elapsed())),
+            root_moves,
+        }
+    }
+
+    fn get_placement_result(&self, request: Request) -> PlacementResult {
+        let start = Instant::now();
+        let (ply, info, _) = self.search(request).unwrap_or_else(|message| panic!("{}", message));
+        let Some(Ply::Place(squares)) = ply else {
+            panic!("{} answered a placement request with {:?}", self.info.name, ply);
+        };
+
+        PlacementResult {
+            squares,
+            eval: info.score,
+            time_spent: Some(info.time.unwrap_or(start.elapsed())),
+        }
+    }
+
+    fn get_options(&self) -> Vec<String> {
+        self.options.clone()
+    }
+    // Only unknown names are caught here. The protocol has no answer to setoption, so a value
+    // the program rejects is at best reported in an info string, which is skipped.
+    fn set_option(&mut self, name: &str, value: &str) -> Result<(), String> {
+        if !self.options.iter().any(|x| x.eq_ignore_ascii_case(name)) {
+            return Err(format!("{} has no option {}!", self.info.name, name));
+        }
+        self.send(Command::SetOption {
+            name: name.to_string(),
+            value: value.to_string(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::handle::SearchHandle;
+    use std::sync::Arc;
+
+    // A stand-in program that knows just enough of the protocol
+    fn fake_engine(on_go: &str) -> ExternalEngine {
+        let script = format!(
+            "while read line; do case \"$line\" in \
+                sei) echo 'id name fake'; echo 'id evalrange -1 1'; echo 'option name Hash'; echo seiok;; \
+                go*) {};; \
+                quit) exit;; \
+            esac; done",
+            on_go
+        );
+        ExternalEngine::spawn("sh", &["-c", &script]).unwrap()
+    }
+
+    #[test]
+    fn handshake() {
+        let engine = fake_engine("");
+        let info = engine.get_info();
+        assert_eq!(info.name, "fake");
+        assert_eq!(info.eval_range, (-1, 1));
+        assert_eq!(engine.get_options(), vec![String::from("Hash")]);
+    }
+
+    #[test]
+    fn options() {
+        let mut engine = fake_engine("");
+        assert!(engine.set_option("hash", "64").is_ok());
+        assert!(engine.set_option("Contempt", "16").is_err());
+    }
+
+    #[test]
+    fn search() {
+        let engine = fake_engine("echo 'info depth 3 score 1 pv B3-A3^A4'; echo 'bestmove B3-A3^A4'");
+        let board = Board::new([B3, C2], [C4, D3]);
+        let result = engine.get_search_result(board.get_request(SearchLimits::clock(P1, Duration::from_secs(1))));
+        assert_eq!(result.mv, Move { from: B3, to: A3, at: Some(A4) });
+        assert_eq!(result.eval, Some(Score::Eval(1)));
+        assert_eq!(result.depth_searched, Some(3));
+        assert_eq!(result.pv, Some(vec![result.mv]));
+    }
+
+    #[test]
+    fn info_is_forwarded() {
+        let engine = fake_engine("echo 'info depth 1 score 0 time 5 pv B3-A3^A4'; echo 'info string thinking'; echo 'info depth 2 score 1 nodes 80 time 10'; echo 'bestmove B3-A3^A4'");
+        let depths = Arc::new(Mutex::new(vec![]));
+        let board = Board::new([B3, C2], [C4, D3]);
+        let mut request = board.get_request(SearchLimits::depth(2));
+        let seen = depths.clone();
+        request.sink = Some(InfoSink::new(move |x| seen.lock().unwrap().push((x.depth, x.nodes))));
+        engine.get_search_result(request);
+        assert_eq!(*depths.lock().unwrap(), vec![(1, 0), (2, 80)]);
+    }
+
+    #[test]
+    fn multi_pv() {
+        let engine = fake_engine(
+            "echo 'info multipv 1 depth 1 score 0 pv B3-B4^B5'; echo 'info multipv 2 depth 1 score -2 pv C2-C3^C4'; \
+             echo 'info multipv 1 depth 2 score 1 time 10 pv B3-A3^A4 C4-C5^C4'; echo 'info multipv 2 depth 2 score 0 pv B3-B4^B5'; \
+             echo 'bestmove B3-A3^A4'",
+        );
+        let board = Board::new([B3, C2], [C4, D3]);
+        let result = engine.get_search_result(board.get_request(SearchLimits { multi_pv: Some(2), ..SearchLimits::depth(2) }));
+        assert_eq!(result.depth_searched, Some(2));
+        assert_eq!(result.root_moves.iter().map(|x| (x.mv.to_string(), x.eval)).collect::<Vec<(String, Score)>>(), vec![
+            (String::from("B3-A3^A4"), Score::Eval(1)),
+            (String::from("B3-B4^B5"), Score::Eval(0)),
+        ]);
+    }
+
+    #[test]
+    fn placement() {
+        let engine = fake_engine("echo 'bestmove C3,B3'");
+        let result = engine.get_placement_result(Board::empty().get_request(SearchLimits::movetime(Duration::from_secs(1))));
+        assert_eq!(result.squares, [C3, B3]);
+    }
+
+    #[test]
+    #[should_panic(expected = "fake exited")]
+    fn crash() {
+        let engine = fake_engine("exit");
+        engine.get_move(Board::new([B3, C2], [C4, D3]).get_request(SearchLimits::depth(4)));
+    }
+
+    #[test]
+    #[should_panic(expected = "fake failed: out of memory")]
+    fn error() {
+        let engine = fake_engine("echo 'error out of memory'");
+        engine.get_move(Board::new([B3, C2], [C4, D3]).get_request(SearchLimits::depth(4)));
+    }
+
+    #[test]
+    fn stop() {
+        // Only answers once told to stop
+        let script = "while read line; do case \"$line\" in \
+                sei) echo seiok;; \
+                stop) echo 'bestmove B3-A3^A4';; \
+                quit) exit;; \
+            esac; done";
+        let engine = ExternalEngine::spawn("sh", &["-c", script]).unwrap();
+        let board = Board::new([B3, C2], [C4, D3]);
+        let handle = SearchHandle::search(Box::new(engine), board, SearchLimits { infinite: true, ..Default::default() }, None);
+        assert!(handle.wait_timeout(Duration::from_millis(100)).is_none());
+        handle.stop();
+        let (_, result) = handle.wait().unwrap();
+        assert_eq!(result.mv, Move { from: B3, to: A3, at: Some(A4) });
+    }
+
+    #[test]
+    fn missing_program() {
+        assert!(ExternalEngine::spawn("./there-is-no-such-engine", &[]).is_err());
+    }
+}
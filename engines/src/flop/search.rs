@@ -1,121 +1,105 @@
-use std::isize::MIN;
 use std::ops::Add;
-use std::time::Duration;
 use std::time::Instant;
 
-use crate::BenchmarkRequest;
-use crate::helpers::print_with_timestamp;
-use crate::helpers::turn::*;
-use crate::flop::board_rep::*;
-use crate::flop::eval::*;
-use crate::models::SearchResult;
+use crate::helpers::{get_loss_value, print_with_timestamp, Deadline, SearchStats};
+use crate::core::board_rep::*;
+use crate::core::eval::*;
+use crate::models::{Request, RootMoveResult, Score, SearchResult};
 
-use super::convert_move;
+use crate::core::{convert_move, convert_pv};
+use crate::core::search::{SearchRequest, CHECK_CLOCK_EVERY};
 
-const BIG_ENOUGH_VALUE:isize = 10000;
-#[derive(Debug)]
-pub struct SearchRequest{
-    pub position:Board,
-    pub max_depth:usize,
-    pub time_left:Option<Duration>,
-    pub debug: bool,
+// What the searchers keep track of besides the board
+struct SearchState {
+    stats: SearchStats,
+    // Depth of the current iteration, to know how far from the root a node is
+    depth: usize,
+    deadline: Deadline,
+    running: bool,
 }
-
-fn prepare_to_benchmark(searcher: fn(&mut Board, usize) -> isize) -> impl Fn(BenchmarkRequest) -> SearchResult {
-    move |benchmark_request| {
-        let internal_board = Board {
-            blocks: benchmark_request.position.blocks,
-            workers: benchmark_request.position.workers,
-            turn: benchmark_request.position.turn,
-            moves: vec![],
-        };
-
-        let request = SearchRequest {
-            position: internal_board,
-            max_depth: benchmark_request.max_depth,
-            time_left: None,
-            debug: true,
-        };
-        get_move(request, searcher)
+impl SearchState {
+    // Counts a node and tells whether the search has to give up
+    fn visit(&mut self, depth_left: usize) -> bool {
+        self.stats.visit(self.depth - depth_left);
+        let nodes = self.stats.nodes;
+        if self.running && (self.deadline.out_of_nodes(nodes) || (nodes.is_multiple_of(CHECK_CLOCK_EVERY) && self.deadline.should_stop(nodes))) {
+            self.running = false;
+        }
+        !self.running
     }
 }
 
-pub fn flop_v1_benchmark(br:BenchmarkRequest) -> SearchResult{
-    prepare_to_benchmark(negamax)(br)
+pub fn flop_v1_benchmark(request:Request) -> SearchResult{
+    get_move(SearchRequest::from_request(request, true), negamax)
 }
 
-pub fn flop_v2_benchmark(br:BenchmarkRequest) -> SearchResult{
-    prepare_to_benchmark(alpha_beta_first_call)(br)
+pub fn flop_v2_benchmark(request:Request) -> SearchResult{
+    get_move(SearchRequest::from_request(request, true), alpha_beta_first_call)
 }
 
-fn negamax (node:&mut Board, depth:usize) -> isize{
-    let color =
-        match node.turn {
-            W => 1,
-            U => -1,
-            _ => unreachable!(),
-        };
-    match node.moves.last() {
-        Some(last) => {
-            if node.blocks[last.to] == 3 {
-                return -BIG_ENOUGH_VALUE - depth as isize;
-            }
-        }
-        None => {}
-    }   
+// Searchers return the value of the node along with the line they expect to be played from it
+fn negamax (node:&mut Board, depth:usize, state:&mut SearchState) -> (isize, Vec<Move>){
+    if state.visit(depth) {
+        return (0, vec![]);
+    }
+    let color = node.get_color();
+    if node.game_is_over() {
+        return (get_loss_value(state.depth - depth), vec![]);
+    }
     if depth == 0{
-        return color * eval(node);      
+        return (color * eval(node), vec![]);      
     }
-    let mut value = -BIG_ENOUGH_VALUE * 100;
-    let moves = node.generate_moves();
-    if moves.len() == 0{
-        value = -BIG_ENOUGH_VALUE - depth as isize;
+    let mut value = -Score::WIN_VALUE * 100;
+    let mut pv = vec![];
+    let mut moves = MoveList::new();
+    node.generate_moves(&mut moves);
+    if moves.is_empty(){
+        value = get_loss_value(state.depth - depth);
     }
-    for mv in moves{
+    for &mv in moves.iter() {
         node.make_move(mv);
-        let new_value = -negamax(node, depth-1);
-        if new_value > value{
-            value = new_value;
+        let (child_value, child_pv) = negamax(node, depth-1, state);
+        if -child_value > value{
+            value = -child_value;
+            pv = vec![mv];
+            pv.extend(child_pv);
         }
         node.undo_move(mv);
     }
-    value
+    (value, pv)
 
 }
 
-fn alpha_beta_first_call(node:&mut Board, depth:usize) -> isize{
-    alpha_beta_prunning(node, depth, -BIG_ENOUGH_VALUE, BIG_ENOUGH_VALUE)
+fn alpha_beta_first_call(node:&mut Board, depth:usize, state:&mut SearchState) -> (isize, Vec<Move>){
+    alpha_beta_prunning(node, depth, -Score::WIN_VALUE, Score::WIN_VALUE, state)
 }
 
-fn alpha_beta_prunning (node:&mut Board, depth:usize, mut alpha:isize, beta:isize) -> isize{
-    let color =
-        match node.turn {
-            W => 1,
-            U => -1,
-            _ => unreachable!(),
-        };
-    match node.moves.last() {
-        Some(last) => {
-            if node.blocks[last.to] == 3 {
-                return -BIG_ENOUGH_VALUE - depth as isize;
-            }
-        }
-        None => {}
-    }   
+fn alpha_beta_prunning (node:&mut Board, depth:usize, mut alpha:isize, beta:isize, state:&mut SearchState) -> (isize, Vec<Move>){
+    if state.visit(depth) {
+        return (0, vec![]);
+    }
+    let color = node.get_color();
+    if node.game_is_over() {
+        return (get_loss_value(state.depth - depth), vec![]);
+    }
     if depth == 0{
-        return color * eval(node);      
+        return (color * eval(node), vec![]);      
     }
-    let mut value = -BIG_ENOUGH_VALUE * 100;
-    let moves = node.generate_moves();
-    if moves.len() == 0{
-        value = -BIG_ENOUGH_VALUE - depth as isize;
+    let mut value = -Score::WIN_VALUE * 100;
+    let mut pv = vec![];
+    let mut moves = MoveList::new();
+    node.generate_moves(&mut moves);
+    if moves.is_empty(){
+        value = get_loss_value(state.depth - depth);
     }
-    for mv in moves{
+    for &mv in moves.iter() {
         node.make_move(mv);
-        let new_value = -alpha_beta_prunning(node, depth-1, -beta, -alpha);
+        let (child_value, child_pv) = alpha_beta_prunning(node, depth-1, -beta, -alpha, state);
         node.undo_move(mv);
-        if new_value > value{
-            value = new_value;
+        if -child_value > value{
+            value = -child_value;
+            pv = vec![mv];
+            pv.extend(child_pv);
         }
         if value > alpha{
             alpha = value;
@@ -124,75 +108,116 @@ fn alpha_beta_prunning (node:&mut Board, depth:usize, mut alpha:isize, beta:isiz
             break;
         }
     }
-    value
+    (value, pv)
 
 }
 
 
-fn get_move(request: SearchRequest, searcher:fn(&mut Board, usize) -> isize) -> SearchResult{ 
-    let thinking_time = request.time_left.unwrap_or(Duration::from_secs(10000));
-
+fn get_move(request: SearchRequest, searcher:fn(&mut Board, usize, &mut SearchState) -> (isize, Vec<Move>)) -> SearchResult{ 
     let current_time = Instant::now();
-    let limit_time = current_time.add(thinking_time);
-
-    let mut running = true;
-    let mut board = Board {
-        blocks: request.position.blocks,
-        workers: request.position.workers,
-        turn: request.position.turn,
-        moves: vec![],
+    let deadline = Deadline {
+        stop_at: request.time_left.map(|x| current_time.add(x)),
+        max_nodes: request.max_nodes,
+        control: request.control.clone(),
     };
-    let available_moves = board.generate_moves();
+
+    let mut board = Board::new(request.position.blocks, request.position.workers, request.position.turn);
+    let mut available_moves = MoveList::new();
+    board.generate_moves(&mut available_moves);
     let num_moves = available_moves.len();
     let best_move = available_moves[0];
-    let mut scores: Vec<isize> = vec![MIN; num_moves];
+    let mut scores: Vec<isize> = vec![isize::MIN; num_moves];
+    // The line each root move leads to, starting with the move itself
+    let mut pvs: Vec<Vec<Move>> = vec![vec![]; num_moves];
     let mut depth = 0;
+    let mut depth_searched = 0;
+    // The first iteration always finishes so there is a move to play
+    let mut state = SearchState {
+        stats: SearchStats::default(),
+        depth: 0,
+        deadline: Deadline::default(),
+        running: true,
+    };
 
-    while running {
+    while state.running {
         depth += 1;
+        state.depth = depth;
+        if depth == 2 {
+            state.deadline = deadline.clone();
+        }
         if request.debug{
             print_with_timestamp(&format!("Starting depth: {}", depth));
         }
+        let mut iteration_scores: Vec<isize> = vec![isize::MIN; num_moves];
+        let mut iteration_pvs: Vec<Vec<Move>> = vec![vec![]; num_moves];
         for i in 0..num_moves {
             board.make_move(available_moves[i]);
-            scores[i] = -searcher(&mut board, depth - 1);
+            let (value, pv) = searcher(&mut board, depth - 1, &mut state);
             board.undo_move(available_moves[i]);
+            iteration_scores[i] = -value;
+            iteration_pvs[i] = vec![available_moves[i]];
+            iteration_pvs[i].extend(pv);
+            if !state.running {
+                break;
+            }
             if request.debug{
                 print_with_timestamp(&format!("Move {} {:?} evaluated. Score: {}",
-                 i+1, available_moves[i], scores[i]));
-            }
-            if let Some(_duration) = request.time_left {
-                if Instant::now() > limit_time {
-                    running = false;
-                }
+                 i+1, available_moves[i], iteration_scores[i]));
             }
         }
 
-        if depth == request.max_depth {
-            running = false;
+        // An unfinished iteration hasn't looked at every move, so its scores are dropped
+        if state.running {
+            scores = iteration_scores;
+            pvs = iteration_pvs;
+            depth_searched = depth;
+            if let Some(sink) = &request.sink {
+                let (index, &score) = scores.iter().enumerate().max_by_key(|&(_, value)| value).unwrap();
+                sink.send(&state.stats.get_info(depth, score, current_time.elapsed(), convert_pv(&board, &pvs[index]), None));
+            }
+        }
+        if depth >= request.max_depth {
+            state.running = false;
         } 
 
     }
     let end_time = Instant::now();
     let time_spent_thinking = end_time - current_time;
+
+    // Reversing the stable sort puts the last of equal scores first, like max_by_key below
+    let mut ranking: Vec<usize> = (0..num_moves).collect();
+    ranking.sort_by_key(|&i| scores[i]);
+    ranking.reverse();
+    let root_moves = ranking
+        .into_iter()
+        .take(request.multi_pv)
+        .map(|i| RootMoveResult {
+            mv: convert_move(&board, available_moves[i]),
+            eval: Score::from_value(scores[i]),
+            pv: convert_pv(&board, &pvs[i]),
+        })
+        .collect();
+
     if let Some((index, &max_value)) = scores.iter().enumerate().max_by_key(|&(_, value)| value) {
         // dbg!(&available_moves);
         // dbg!(&scores);
         let mv = available_moves[index];
         SearchResult {
-            mv: convert_move(board, mv),
-            eval: Some(max_value),
-            pv: None,
+            mv: convert_move(&board, mv),
+            eval: Some(Score::from_value(max_value)),
+            pv: Some(convert_pv(&board, &pvs[index])),
             time_spent: Some(time_spent_thinking),
-            depth_searched: Some(depth),
+            depth_searched: Some(depth_searched),
+            root_moves,
         }
     } else {
         SearchResult {
-            mv: convert_move(board, best_move),
+            mv: convert_move(&board, best_move),
             eval: None,
             pv: None,
             time_spent: Some(time_spent_thinking),
-            depth_searched: Some(depth),
+            depth_searched: Some(depth_searched),
+            root_moves,
         }
     }
 }
@@ -203,33 +228,39 @@ pub fn get_best_move(request: SearchRequest) -> SearchResult{
 
 #[cfg(test)]
 mod tests {
+    use std::sync::Arc;
+
     use super::*;
     use crate::helpers::squares::*;
+    use crate::helpers::turn::*;
 
     fn get_best_move_test(board:Board, depth:usize) -> Move{
         let request = SearchRequest{
             position:board,
             max_depth: depth,
             time_left: None,
+            max_nodes: None,
+            multi_pv: 1,
+            control: Arc::default(),
+            sink: None,
             debug: false,
         };
         let mv = get_best_move(request).mv;
-        Move{from: mv.from, to:mv.to, build: mv.build.unwrap_or(mv.from)}
+        Move{from: mv.from.into(), to:mv.to.into(), build: mv.at.unwrap_or(mv.from).into()}
     }
 
     #[test]
     fn m1(){
         let board = 
-            Board {
-                blocks: [0, 0, 0, 0, 0,
-                         0, 3, 0, 0, 0,
-                         0, 2, 0, 0, 0,
-                         0, 0, 0, 0, 0,
-                         0, 0, 0, 0, 0],
-                workers: [C3, C2, C4, B3],
-                turn: W,
-                moves: vec![],
-            };
+            Board::new(
+                [0, 0, 0, 0, 0,
+                 0, 3, 0, 0, 0,
+                 0, 2, 0, 0, 0,
+                 0, 0, 0, 0, 0,
+                 0, 0, 0, 0, 0],
+                [C3, C2, C4, B3],
+                W,
+            );
         let depth = 1;
         let best_move = Move {from: C2, to:B2, build: C2};
         assert_eq!(get_best_move_test(board, depth), best_move);
@@ -237,16 +268,15 @@ mod tests {
     #[test]
     fn m1_5(){
         let board = 
-            Board {
-                blocks: [0, 0, 0, 0, 0,
-                         0, 0, 0, 2, 2,
-                         0, 0, 0, 0, 0,
-                         0, 0, 0, 2, 1,
-                         0, 0, 0, 3, 0],
-                workers: [C3, A4, A5, E5],
-                turn: W,
-                moves: vec![],
-            };
+            Board::new(
+                [0, 0, 0, 0, 0,
+                 0, 0, 0, 2, 2,
+                 0, 0, 0, 0, 0,
+                 0, 0, 0, 2, 1,
+                 0, 0, 0, 3, 0],
+                [C3, A4, A5, E5],
+                W,
+            );
         let depth = 2;
         let best_move = Move {from: C3, to:C4, build: D5};
         assert_eq!(get_best_move_test(board, depth), best_move);
@@ -254,16 +284,15 @@ mod tests {
     #[test]
     fn prevent_m1(){
         let board = 
-            Board {
-                blocks: [0, 0, 0, 0, 0,
-                         0, 3, 0, 0, 0,
-                         0, 2, 0, 0, 0,
-                         0, 0, 0, 0, 0,
-                         0, 0, 0, 0, 0],
-                workers: [D1, E5, C2, D2],
-                turn: W,
-                moves: vec![],
-            };
+            Board::new(
+                [0, 0, 0, 0, 0,
+                 0, 3, 0, 0, 0,
+                 0, 2, 0, 0, 0,
+                 0, 0, 0, 0, 0,
+                 0, 0, 0, 0, 0],
+                [D1, E5, C2, D2],
+                W,
+            );
         let depth = 2;
         let best_move = Move {from: D1, to:C1, build: B2};
         assert_eq!(get_best_move_test(board, depth), best_move);
@@ -271,16 +300,15 @@ mod tests {
     #[test]
     fn mi2_dw (){
         let board = 
-            Board {
-                blocks: [0, 0, 0, 0, 0,
-                         0, 0, 1, 0, 0,
-                         0, 0, 2, 0, 0,
-                         0, 0, 2, 3, 0,
-                         0, 0, 0, 0, 0],
-                workers: [B3, C2, D5, E5],
-                turn: W,
-                moves: vec![],
-            };
+            Board::new(
+                [0, 0, 0, 0, 0,
+                 0, 0, 1, 0, 0,
+                 0, 0, 2, 0, 0,
+                 0, 0, 2, 3, 0,
+                 0, 0, 0, 0, 0],
+                [B3, C2, D5, E5],
+                W,
+            );
         let depth = 3;
         let best_move = Move {from: B3, to:C3, build: D3};
         assert_eq!(get_best_move_test(board, depth), best_move);
@@ -289,16 +317,15 @@ mod tests {
     #[test]
     fn mi2_dw_blue (){
         let board = 
-            Board {
-                blocks: [0, 0, 0, 0, 0,
-                         0, 0, 1, 0, 0,
-                         0, 0, 2, 0, 0,
-                         0, 0, 2, 3, 0,
-                         0, 0, 0, 0, 0],
-                workers: [D5, E5, B3, C2],
-                turn: U,
-                moves: vec![],
-            };
+            Board::new(
+                [0, 0, 0, 0, 0,
+                 0, 0, 1, 0, 0,
+                 0, 0, 2, 0, 0,
+                 0, 0, 2, 3, 0,
+                 0, 0, 0, 0, 0],
+                [D5, E5, B3, C2],
+                U,
+            );
         let depth = 3;
         let best_move = Move {from: B3, to:C3, build: D3};
         assert_eq!(get_best_move_test(board, depth), best_move);
@@ -307,16 +334,15 @@ mod tests {
     #[test]
     fn mi2_fa (){
         let board = 
-            Board {
-                blocks: [0, 0, 0, 0, 2,
-                         0, 0, 0, 2, 2,
-                         0, 0, 2, 0, 0,
-                         0, 0, 0, 0, 0,
-                         0, 0, 0, 0, 0],
-                workers: [C3, A4, C4, B3],
-                turn: W,
-                moves: vec![],
-            };
+            Board::new(
+                [0, 0, 0, 0, 2,
+                 0, 0, 0, 2, 2,
+                 0, 0, 2, 0, 0,
+                 0, 0, 0, 0, 0,
+                 0, 0, 0, 0, 0],
+                [C3, A4, C4, B3],
+                W,
+            );
         let depth = 3;
         let best_move = Move {from: C3, to:B4, build: A5};
         assert_eq!(get_best_move_test(board, depth), best_move);
@@ -324,16 +350,15 @@ mod tests {
     #[test]
     fn m2_5 (){
         let board = 
-            Board {
-                blocks: [0, 0, 0, 0, 0,
-                         0, 0, 0, 0, 0,
-                         0, 0, 0, 4, 1,
-                         4, 4, 0, 2, 0,
-                         4, 0, 1, 3, 0],
-                workers: [C5, D3, E2, D5],
-                turn: W,
-                moves: vec![],
-            };
+            Board::new(
+                [0, 0, 0, 0, 0,
+                 0, 0, 0, 0, 0,
+                 0, 0, 0, 4, 1,
+                 4, 4, 0, 2, 0,
+                 4, 0, 1, 3, 0],
+                [C5, D3, E2, D5],
+                W,
+            );
         let depth = 4;
         let best_move = Move {from: C5, to:D4, build: E3};
         assert_eq!(get_best_move_test(board, depth), best_move);
@@ -341,16 +366,15 @@ mod tests {
     #[test]
     fn stalling (){
         let board = 
-            Board {
-                blocks: [0, 0, 0, 0, 0,
-                         0, 2, 3, 0, 0,
-                         0, 2, 0, 0, 0,
-                         2, 3, 0, 0, 0,
-                         0, 0, 0, 0, 0],
-                workers: [B1, D5, A2, B2],
-                turn: W,
-                moves: vec![],
-            };
+            Board::new(
+                [0, 0, 0, 0, 0,
+                 0, 2, 3, 0, 0,
+                 0, 2, 0, 0, 0,
+                 2, 3, 0, 0, 0,
+                 0, 0, 0, 0, 0],
+                [B1, D5, A2, B2],
+                W,
+            );
         let depth = 4;
         let best_move = Move {from: D5, to:C4, build: B3};
         assert_eq!(get_best_move_test(board, depth), best_move);
@@ -0,0 +1,186 @@
+use std::{
+    any::Any,
+    panic::{self, AssertUnwindSafe},
+    sync::{
+        mpsc::{self, Receiver, RecvTimeoutError},
+        Arc,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+use crate::models::*;
+
+// What a search thread sends back: the engine, so it can search again, and what it found, or
+// the message it panicked with
+type Answer<T> = Result<(Box<dyn Engine>, T), String>;
+
+fn panic_message(payload: Box<dyn Any + Send>) -> String {
+    if let Some(message) = payload.downcast_ref::<&str>() {
+        message.to_string()
+    } else if let Some(message) = payload.downcast_ref::<String>() {
+        message.clone()
+    } else {
+        String::from("unknown panic")
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct SearchProgress {
+    pub nodes: usize,
+    pub elapsed: Duration,
+}
+
+// A search running on its own thread. It can be followed while it runs, stopped early through
+// its SearchControl, and waited for to get the result along with the engine back.
+pub struct SearchHandle<T> {
+    control: Arc<SearchControl>,
+    started: Instant,
+    answer: Receiver<Answer<T>>,
+}
+impl SearchHandle<SearchResult> {
+    pub fn search(engine: Box<dyn Engine>, board: Board, limits: SearchLimits, sink: Option<InfoSink>) -> Self {
+        SearchHandle::start(engine, board, limits, sink, |engine, request| engine.get_search_result(request))
+    }
+}
+impl SearchHandle<PlacementResult> {
+    pub fn place(engine: Box<dyn Engine>, board: Board, limits: SearchLimits, sink: Option<InfoSink>) -> Self {
+        SearchHandle::start(engine, board, limits, sink, |engine, request| engine.get_placement_result(request))
+    }
+}
+impl<T: Send + 'static> SearchHandle<T> {
+    fn start(engine: Box<dyn Engine>, board: Board, limits: SearchLimits, sink: Option<InfoSink>, run: fn(&dyn Engine, Request) -> T) -> Self {
+        let control = Arc::new(SearchControl::default());
+        let (sender, answer) = mpsc::channel();
+
+        let shared = control.clone();
+        thread::spawn(move || {
+            let result = panic::catch_unwind(AssertUnwindSafe(|| {
+                let mut request = board.get_request(limits);
+                request.control = shared;
+                request.sink = sink;
+                run(engine.as_ref(), request)
+            }));
+            // Nobody is listening any more if the search was abandoned
+            let _ = sender.send(result.map(|x| (engine, x)).map_err(panic_message));
+        });
+
+        SearchHandle {
+            control,
+            started: Instant::now(),
+            answer,
+        }
+    }
+
+    // Asks the engine to answer with what it has found so far
+    pub fn stop(&self) {
+        self.control.stop();
+    }
+    // For stopping the search from another thread than the one waiting for it
+    pub fn get_control(&self) -> Arc<SearchControl> {
+        self.control.clone()
+    }
+    pub fn get_progress(&self) -> SearchProgress {
+        SearchProgress {
+            nodes: self.control.get_nodes(),
+            elapsed: self.started.elapsed(),
+        }
+    }
+
+    pub fn wait(self) -> Answer<T> {
+        self.answer
+            .recv()
+            .unwrap_or_else(|_| Err(String::from("search thread died")))
+    }
+    // None if the search is still running once the timeout passes
+    pub fn wait_timeout(&self, timeout: Duration) -> Option<Answer<T>> {
+        match self.answer.recv_timeout(timeout) {
+            Ok(answer) => Some(answer),
+            Err(RecvTimeoutError::Timeout) => None,
+            Err(RecvTimeoutError::Disconnected) => Some(Err(String::from("search thread died"))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::get_engine;
+
+    #[test]
+    fn search() {
+        let board = Board::new([B3, C2], [C4, D3]);
+        let handle = SearchHandle::search(get_engine("spectre").unwrap(), board, SearchLimits::depth(2), None);
+        let (_, result) = handle.wait().unwrap();
+        assert!(board.is_legal(&result.mv));
+        assert_eq!(result.depth_searched, Some(2));
+    }
+
+    #[test]
+    fn info() {
+        for name in ["flop", "spectre", "strange"] {
+            let board = Board::new([B3, C2], [C4, D3]);
+            let (sender, infos) = mpsc::channel();
+            let sink = InfoSink::new(move |x| sender.send(x.clone()).unwrap());
+            let handle = SearchHandle::search(get_engine(name).unwrap(), board, SearchLimits::depth(3), Some(sink));
+            let (_, result) = handle.wait().unwrap();
+
+            let infos: Vec<SearchInfo> = infos.try_iter().collect();
+            assert_eq!(infos.iter().map(|x| x.depth).collect::<Vec<usize>>(), vec![1, 2, 3], "{} reported other depths", name);
+            assert!(infos.windows(2).all(|x| x[0].nodes <= x[1].nodes));
+            let last = infos.last().unwrap();
+            assert!(last.seldepth >= last.depth);
+            assert_eq!(last.pv.first(), Some(&result.mv), "{} reported another best move", name);
+        }
+    }
+
+    #[test]
+    fn zero_depth() {
+        for name in ["flop", "spectre", "strange"] {
+            let board = Board::new([B3, C2], [C4, D3]);
+            let handle = SearchHandle::search(get_engine(name).unwrap(), board, SearchLimits::depth(0), None);
+            let (_, result) = handle.wait_timeout(Duration::from_secs(1)).expect("A depth 0 search ends after one iteration").unwrap();
+            assert_eq!(result.depth_searched, Some(1), "{} searched another depth", name);
+        }
+    }
+
+    #[test]
+    fn placement() {
+        let handle = SearchHandle::place(get_engine("flop").unwrap(), Board::empty(), SearchLimits::default(), None);
+        let (_, result) = handle.wait().unwrap();
+        assert_ne!(result.squares[0], result.squares[1]);
+    }
+
+    #[test]
+    fn stop() {
+        for name in ["flop", "spectre", "strange"] {
+            let board = Board::new([B3, C2], [C4, D3]);
+            let handle = SearchHandle::search(get_engine(name).unwrap(), board, SearchLimits { infinite: true, ..Default::default() }, None);
+            assert!(handle.wait_timeout(Duration::from_millis(200)).is_none(), "{} answered before being stopped", name);
+            assert!(handle.get_progress().nodes > 0);
+
+            handle.stop();
+            let (_, result) = handle.wait_timeout(Duration::from_secs(1)).expect("Stopped searches answer quickly").unwrap();
+            assert!(board.is_legal(&result.mv));
+        }
+    }
+
+    #[test]
+    fn crash() {
+        struct Crasher;
+        impl Engine for Crasher {
+            fn get_info(&self) -> EngineInfo {
+                EngineInfo {
+                    name: String::from("crasher"),
+                    eval_range: (0, 0),
+                }
+            }
+            fn get_search_result(&self, _: Request) -> SearchResult {
+                panic!("Crasher always crashes");
+            }
+        }
+
+        let handle = SearchHandle::search(Box::new(Crasher), Board::new([B3, C2], [C4, D3]), SearchLimits::default(), None);
+        assert_eq!(handle.wait().err(), Some(String::from("Crasher always crashes")));
+    }
+}
@@ -1,10 +1,19 @@
+use std::{
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
 use chrono::Local;
 
+use crate::models::{Move, Score, SearchControl, SearchInfo};
+
 pub mod turn {
     pub const W: u8 = 0;
     pub const U: u8 = 1;
 }
 
+// Names for the squares, mostly used by the tests now that moves are generated from bitboards
+#[allow(dead_code)]
 pub mod squares {
     pub const A1: usize = 0;
     pub const A2: usize = 1;
@@ -45,6 +54,30 @@ pub mod workers {
     pub const U2: usize = 3;
 }
 
+// Value of a position ply plies from the root where the side to move has lost. Counting from the
+// root rather than with the depth left keeps the distance to the end of the game intact on the
+// way back up.
+pub fn get_loss_value(ply: usize) -> isize {
+    Score::Loss(ply).to_value()
+}
+
+// Wins and losses count plies from the root, but a TT entry can be reached again at another ply,
+// so they are stored counting from the entry's own position instead
+pub fn value_to_tt(value: isize, ply: usize) -> isize {
+    match Score::from_value(value) {
+        Score::Eval(_) => value,
+        Score::Win(_) => value + ply as isize,
+        Score::Loss(_) => value - ply as isize,
+    }
+}
+pub fn value_from_tt(value: isize, ply: usize) -> isize {
+    match Score::from_value(value) {
+        Score::Eval(_) => value,
+        Score::Win(_) => value - ply as isize,
+        Score::Loss(_) => value + ply as isize,
+    }
+}
+
 pub fn print_with_timestamp(message: &str) {
     let timestamp = Local::now();
     println!("[{}] {}", timestamp.format("%Y-%m-%d %H:%M:%S"), message);
@@ -52,3 +85,51 @@ pub fn print_with_timestamp(message: &str) {
 
 
 
+
+// The limits a running search checks to know when to give up
+#[derive(Clone, Debug, Default)]
+pub struct Deadline {
+    pub stop_at: Option<Instant>,
+    pub max_nodes: Option<usize>,
+    pub control: Arc<SearchControl>,
+}
+impl Deadline {
+    // Also true once the search was told to stop
+    pub fn out_of_time(&self) -> bool {
+        self.control.is_stopped() || self.stop_at.is_some_and(|x| Instant::now() > x)
+    }
+    // Reads the clock, so searches only call it every few thousand nodes. The node count is
+    // shared with whoever controls the search at the same time.
+    pub fn should_stop(&self, nodes_searched: usize) -> bool {
+        self.control.set_nodes(nodes_searched);
+        self.out_of_time()
+    }
+    pub fn out_of_nodes(&self, nodes_searched: usize) -> bool {
+        self.max_nodes.is_some_and(|x| nodes_searched >= x)
+    }
+}
+
+// Counters a search keeps as it goes
+#[derive(Clone, Copy, Debug, Default)]
+pub struct SearchStats {
+    pub nodes: usize,
+    pub seldepth: usize,
+}
+impl SearchStats {
+    pub fn visit(&mut self, ply: usize) {
+        self.nodes += 1;
+        self.seldepth = self.seldepth.max(ply);
+    }
+    pub fn get_info(&self, depth: usize, score: isize, elapsed: Duration, pv: Vec<Move>, hashfull: Option<usize>) -> SearchInfo {
+        SearchInfo {
+            depth,
+            seldepth: self.seldepth,
+            score: Score::from_value(score),
+            nodes: self.nodes,
+            nps: SearchInfo::get_nps(self.nodes, elapsed),
+            elapsed,
+            pv,
+            hashfull,
+        }
+    }
+}
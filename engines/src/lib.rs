@@ -1,3 +1,4 @@
+mod core;
 mod helpers;
 mod models;
 
@@ -11,16 +12,23 @@ mod strange;
 
 const ENGINE_REGISTRY: Map<&'static str, fn() -> Box<dyn Engine>> = phf_map! {
     "flop" => || Box::new(flop::new()),
-    "spectre" => || Box::new(spectre::Spectre::new()),
-    "strange" => || Box::new(strange::Strange::new()),
+    "spectre" => || Box::new(spectre::new()),
+    "strange" => || Box::new(strange::new()),
 };
 
 // Public
 
 pub use models::*;
+pub mod protocol;
+pub mod external;
+pub mod handle;
+
+pub use flop::{flop_v1_benchmark, flop_v2_benchmark};
+pub use spectre::{spectre_v1_benchmark, spectre_v2_benchmark};
+pub use strange::strange_v1_benchmark;
 
 pub fn get_engine_names() -> Vec<&'static str> {
-    ENGINE_REGISTRY.keys().map(|x| *x).collect()
+    ENGINE_REGISTRY.keys().copied().collect()
 }
 pub fn get_engine(name: &str) -> Option<Box<dyn Engine>> {
     if let Some(constructor) = ENGINE_REGISTRY.get(name) {
@@ -28,4 +36,53 @@ pub fn get_engine(name: &str) -> Option<Box<dyn Engine>> {
     } else {
         None
     } 
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pv() {
+        for name in ["flop", "spectre", "strange"] {
+            let board = Board::new([C2, C3], [C4, C5]);
+            let result = get_engine(name).unwrap().get_search_result(board.get_request(SearchLimits::depth(3)));
+            let pv = result.pv.unwrap();
+            assert_eq!(pv.first(), Some(&result.mv), "{} played another move than its variation", name);
+            assert!(pv.len() <= 3);
+
+            let mut position = board;
+            for mv in pv {
+                assert!(position.try_apply_move(mv).is_ok(), "{} is not legal in {}'s variation", mv, name);
+            }
+        }
+    }
+
+    #[test]
+    fn multi_pv() {
+        for name in ["flop", "spectre", "strange"] {
+            let board = Board::new([C2, C3], [C4, C5]);
+            let engine = get_engine(name).unwrap();
+            let best = engine.get_search_result(board.get_request(SearchLimits::depth(3)));
+            let result = engine.get_search_result(board.get_request(SearchLimits { multi_pv: Some(4), ..SearchLimits::depth(3) }));
+
+            let root_moves = result.root_moves;
+            assert_eq!(root_moves.len(), 4, "{} ranked another number of moves", name);
+            assert_eq!((root_moves[0].mv, Some(root_moves[0].eval)), (best.mv, best.eval), "{} ranked another move first", name);
+            assert!(root_moves.windows(2).all(|x| x[0].eval >= x[1].eval && x[0].mv != x[1].mv));
+            assert!(root_moves.iter().all(|x| x.pv.first() == Some(&x.mv)));
+        }
+    }
+
+    #[test]
+    fn win_distance() {
+        // C2 can climb to B2 right away, however deep the search goes
+        let board: Board = "00000/03000/02000/00000/00000 C3,C2 C4,B3 P1".parse().unwrap();
+        for name in ["flop", "spectre", "strange"] {
+            for depth in [1, 3] {
+                let result = get_engine(name).unwrap().get_search_result(board.get_request(SearchLimits::depth(depth)));
+                assert_eq!(result.eval, Some(Score::Win(1)), "{} at depth {}", name, depth);
+            }
+        }
+    }
+}
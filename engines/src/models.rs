@@ -1,6 +1,12 @@
 use std::{
+    cmp::Ordering as Order,
     fmt::Display,
-    ops::{Index, IndexMut},
+    ops::{Index, IndexMut, Neg},
+    str::FromStr,
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        Arc,
+    },
     time::Duration,
 };
 
@@ -8,14 +14,32 @@ pub use Blocks::*;
 pub use Square::*;
 pub use Turn::*;
 
-use crate::helpers::workers;
-
 // Engine model
 
-pub trait Engine {
+pub trait Engine: Send {
     fn get_info(&self) -> EngineInfo;
     fn get_search_result(&self, request: Request) -> SearchResult;
 
+    // Engines without their own placement logic take the free squares with the most
+    // neighbours, closest to the centre first
+    fn get_placement_result(&self, request: Request) -> PlacementResult {
+        let distance_to_centre = |square: Square| {
+            let index: usize = square.into();
+            (index / 5).abs_diff(2) + (index % 5).abs_diff(2)
+        };
+        let mut free: Vec<Square> = Square::squares()
+            .into_iter()
+            .filter(|&square| request.workers[square].is_none() && request.blocks[square] != T3 && request.blocks[square] != T4)
+            .collect();
+        free.sort_by_key(|&square| (std::cmp::Reverse(square.get_neighbours().len()), distance_to_centre(square)));
+
+        PlacementResult {
+            squares: [free[0], free[1]],
+            eval: None,
+            time_spent: None,
+        }
+    }
+
     fn get_move(&self, request: Request) -> Move {
         let SearchResult {
             mv,
@@ -23,9 +47,21 @@ pub trait Engine {
             pv: _,
             depth_searched: _,
             time_spent: _,
+            root_moves: _,
         } = self.get_search_result(request);
         mv
     }
+    fn get_placement(&self, request: Request) -> [Square ; 2] {
+        self.get_placement_result(request).squares
+    }
+
+    // Engines with settings that can be changed from outside override this and set_option
+    fn get_options(&self) -> Vec<String> {
+        vec![]
+    }
+    fn set_option(&mut self, name: &str, _value: &str) -> Result<(), String> {
+        Err(format!("{} has no option {}!", self.get_info().name, name))
+    }
 }
 
 // Board models
@@ -52,9 +88,9 @@ impl Blocks {
         }
     }
 }
-impl Into<usize> for Blocks {
-    fn into(self) -> usize {
-        match self {
+impl From<Blocks> for usize {
+    fn from(value: Blocks) -> Self {
+        match value {
             T0 => 0,
             T1 => 1,
             T2 => 2,
@@ -63,8 +99,22 @@ impl Into<usize> for Blocks {
         }
     }
 }
+impl TryFrom<usize> for Blocks {
+    type Error = String;
+
+    fn try_from(value: usize) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(T0),
+            1 => Ok(T1),
+            2 => Ok(T2),
+            3 => Ok(T3),
+            4 => Ok(T4),
+            _ => Err(format!("{} is not a valid height! Range is 0 <= value <= 4.", value)),
+        }
+    }
+}
 
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
 pub struct Board {
     blocks: [Blocks; 25],
     workers: [Option<Worker>; 25],
@@ -72,28 +122,35 @@ pub struct Board {
     victory: Option<Turn>,
 }
 impl Board {
+    // Board before the setup phase, where P1 and then P2 place their two workers
+    pub fn empty() -> Self {
+        Self {
+            blocks: Default::default(),
+            workers: Default::default(),
+            turn: P1,
+            victory: None,
+        }
+    }
     pub fn new(workers_p1: [Square ; 2], workers_p2: [Square ; 2]) -> Self {
         Board::new_custom(Default::default(), workers_p1, workers_p2, Default::default())
     }
     pub fn new_custom(blocks: [Blocks ; 25], workers_p1: [Square ; 2], workers_p2: [Square ; 2], turn: Turn) -> Self {
-        assert_ne!(workers_p1[0], workers_p1[1], "Can't have two workers in the same square {}!", workers_p1[0]);
-        assert_ne!(workers_p1[0], workers_p2[0], "Can't have two workers in the same square {}!", workers_p1[0]);
-        assert_ne!(workers_p1[0], workers_p2[1], "Can't have two workers in the same square {}!", workers_p1[0]);
-        assert_ne!(workers_p1[1], workers_p2[0], "Can't have two workers in the same square {}!", workers_p1[1]);
-        assert_ne!(workers_p1[1], workers_p2[1], "Can't have two workers in the same square {}!", workers_p1[1]);
-        assert_ne!(workers_p2[0], workers_p2[1], "Can't have two workers in the same square {}!", workers_p2[0]);
-
-        assert_ne!(blocks[workers_p1[0]], T4, "Can't place worker on T4 blocks at {}", workers_p1[0]);
-        assert_ne!(blocks[workers_p1[1]], T4, "Can't place worker on T4 blocks at {}", workers_p1[1]);
-        assert_ne!(blocks[workers_p2[0]], T4, "Can't place worker on T4 blocks at {}", workers_p2[0]);
-        assert_ne!(blocks[workers_p2[1]], T4, "Can't place worker on T4 blocks at {}", workers_p2[1]);
-
+        Board::try_new_custom(blocks, workers_p1, workers_p2, turn).unwrap_or_else(|error| panic!("{}", error))
+    }
+    pub fn try_new_custom(blocks: [Blocks ; 25], workers_p1: [Square ; 2], workers_p2: [Square ; 2], turn: Turn) -> Result<Self, RulesError> {
         let mut workers: [Option<Worker>; 25] = Default::default();
-        workers[workers_p1[0]] = Some(Worker { turn: Turn::P1 });
-        workers[workers_p1[1]] = Some(Worker { turn: Turn::P1 });
-        workers[workers_p2[0]] = Some(Worker { turn: Turn::P2 });
-        workers[workers_p2[1]] = Some(Worker { turn: Turn::P2 });
-        
+        for (squares, turn) in [(workers_p1, P1), (workers_p2, P2)] {
+            for square in squares {
+                if workers[square].is_some() {
+                    return Err(RulesError::Occupied(square));
+                }
+                if blocks[square] == T4 {
+                    return Err(RulesError::Dome(square));
+                }
+                workers[square] = Some(Worker { turn });
+            }
+        }
+
         let mut board = Self {
             blocks,
             workers,
@@ -102,51 +159,247 @@ impl Board {
         };
 
         board.check_normal_victory();
-        board.check_smother_victory();
-        if board.victory.is_some() {
-            panic!("Can't initalize board in won position!");
+        if board.victory.is_none() {
+            board.check_smother_victory();
+        }
+        match board.victory {
+            Some(winner) => Err(RulesError::AlreadyWon(winner)),
+            None => Ok(board),
         }
+    }
 
-        board
+    pub fn place_workers(&mut self, squares: [Square ; 2]) {
+        self.try_place_workers(squares).unwrap_or_else(|error| panic!("{}", error))
     }
+    // Leaves the board untouched when the placement is illegal
+    pub fn try_place_workers(&mut self, squares: [Square ; 2]) -> Result<(), RulesError> {
+        if !self.in_setup() {
+            return Err(RulesError::SetupOver);
+        }
+        if squares[0] == squares[1] {
+            return Err(RulesError::Occupied(squares[1]));
+        }
+        for square in squares {
+            if self.workers[square].is_some() {
+                return Err(RulesError::Occupied(square));
+            }
+            match self.blocks[square] {
+                T3 => return Err(RulesError::ThirdLevel(square)),
+                T4 => return Err(RulesError::Dome(square)),
+                _ => {}
+            }
+        }
 
+        for square in squares {
+            self.workers[square] = Some(Worker { turn: self.turn });
+        }
+        self.next_turn();
+
+        if !self.in_setup() {
+            self.check_smother_victory();
+        }
+        Ok(())
+    }
+    pub fn in_setup(&self) -> bool {
+        self.workers.iter().filter(|x| x.is_some()).count() < 4
+    }
     pub fn apply_move(&mut self, mv: Move) {
-        let Move {
-            from,
-            to,
-            at,
-        } = mv;
+        self.try_apply_move(mv).unwrap_or_else(|error| panic!("{}", error))
+    }
+    // Leaves the board untouched when the move is illegal
+    pub fn try_apply_move(&mut self, mv: Move) -> Result<(), RulesError> {
+        self.check_move(mv)?;
 
-        self.move_worker(from, to);
+        self.move_worker(mv.from, mv.to);
 
         self.check_normal_victory();
         if self.victory.is_some() {
-            return;
+            return Ok(());
         }
 
-        self.build(at.unwrap());
+        self.build(mv.at.expect("Checked moves that don't win have a build"));
         self.next_turn();
 
         self.check_smother_victory();
+        Ok(())
+    }
+    pub fn get_blocks(&self) -> &[Blocks ; 25] {
+        &self.blocks
+    }
+    pub fn get_workers(&self) -> &[Option<Worker> ; 25] {
+        &self.workers
+    }
+    pub fn get_turn(&self) -> Turn {
+        self.turn
     }
-    pub fn get_request<'a>(&'a self, time_left: Duration) -> Request<'a> {
+    pub fn get_victory(&self) -> Option<Turn> {
+        self.victory
+    }
+    pub fn get_request<'a>(&'a self, limits: SearchLimits) -> Request<'a> {
         Request {
             blocks: &self.blocks,
             workers: &self.workers,
             turn: &self.turn,
-            time_left,
+            limits,
+            control: Arc::default(),
+            sink: None,
         }
     }
+    // One line holding the heights row by row from A to E, P1's and P2's worker squares and the
+    // side to move, e.g. "00000/00100/00000/00000/00004 B3,C2 C4,D3 P1". Workers that aren't
+    // placed yet are written as "-".
+    pub fn to_notation(&self) -> String {
+        let heights: Vec<String> = self.blocks
+            .chunks(5)
+            .map(|row| row.iter().map(|&x| usize::from(x).to_string()).collect())
+            .collect();
+        let workers = [P1, P2].map(|turn| {
+            let squares: Vec<String> = Square::squares()
+                .into_iter()
+                .filter(|&square| self.workers[square] == Some(Worker { turn }))
+                .map(|square| square.to_string())
+                .collect();
+            if squares.is_empty() {
+                String::from("-")
+            } else {
+                squares.join(",")
+            }
+        });
 
-    fn build(&mut self, at: Square) {
-        assert!(self.workers[at].is_none(), "Can't build over worker at {}!", at);
+        format!("{} {} {} {}", heights.join("/"), workers[P1], workers[P2], self.turn)
+    }
+    // Every move the side to move can play. Winning moves onto the third level have no build.
+    pub fn legal_moves(&self) -> Vec<Move> {
+        Square::squares()
+            .into_iter()
+            .flat_map(|from| self.legal_moves_from(from))
+            .collect()
+    }
+    // Moves of the worker in `from`, empty when it isn't a worker of the side to move
+    pub fn legal_moves_from(&self, from: Square) -> Vec<Move> {
+        self.legal_destinations(from)
+            .into_iter()
+            .flat_map(|to| {
+                if self.blocks[to] == T3 {
+                    vec![Move { from, to, at: None }]
+                } else {
+                    self.legal_builds(from, to)
+                        .into_iter()
+                        .map(|at| Move { from, to, at: Some(at) })
+                        .collect()
+                }
+            })
+            .collect()
+    }
+    // Squares the worker in `from` can move to
+    pub fn legal_destinations(&self, from: Square) -> Vec<Square> {
+        if self.in_setup() || self.victory.is_some() || self.workers[from] != Some(Worker { turn: self.turn }) {
+            return vec![];
+        }
+        from.get_neighbours()
+            .into_iter()
+            .filter(|&to| self.workers[to].is_none() && self.blocks[from].is_reachable(&self.blocks[to]))
+            .collect()
+    }
+    // Squares the worker in `from` can build on after moving to `to`. The square it left is free.
+    pub fn legal_builds(&self, from: Square, to: Square) -> Vec<Square> {
+        if !self.legal_destinations(from).contains(&to) || self.blocks[to] == T3 {
+            return vec![];
+        }
+        to.get_neighbours()
+            .into_iter()
+            .filter(|&at| (self.workers[at].is_none() || at == from) && self.blocks[at] != T4)
+            .collect()
+    }
+    pub fn is_legal(&self, mv: &Move) -> bool {
+        self.check_move(*mv).is_ok()
+    }
+    // Reads a move for the side to move, where anything that is clear from the position can be
+    // left out: "C3-D4^D5", "D4^D5" when only one worker can reach D4, or even "D4".
+    pub fn parse_move(&self, notation: &str) -> Result<Move, String> {
+        let (from, rest) = match notation.split_once('-') {
+            Some((from, rest)) => (Some(from.parse::<Square>()?), rest),
+            None => (None, notation),
+        };
+        let (to, at) = match rest.split_once('^') {
+            Some((to, at)) => (to.parse::<Square>()?, Some(at.parse::<Square>()?)),
+            None => (rest.parse::<Square>()?, None),
+        };
+
+        let matching: Vec<Move> = self.legal_moves()
+            .into_iter()
+            .filter(|mv| from.is_none_or(|x| x == mv.from) && mv.to == to && (at.is_none() || at == mv.at))
+            .collect();
+
+        match matching[..] {
+            [mv] => Ok(mv),
+            [] => Err(format!("{} is not a legal move for {}!", notation, self.turn)),
+            _ => {
+                let candidates: Vec<String> = matching.iter().map(|x| x.to_string()).collect();
+                Err(format!("{} is ambiguous, it could be {}!", notation, candidates.join(", ")))
+            }
+        }
+    }
+
+    fn check_move(&self, mv: Move) -> Result<(), RulesError> {
+        let Move {
+            from,
+            to,
+            at,
+        } = mv;
+
+        if self.in_setup() {
+            return Err(RulesError::InSetup);
+        }
+        if self.victory.is_some() {
+            return Err(RulesError::GameOver);
+        }
 
+        match self.workers[from] {
+            None => return Err(RulesError::NoWorker(from)),
+            Some(Worker { turn }) if turn != self.turn => return Err(RulesError::WrongWorker(from)),
+            Some(_) => {}
+        }
+        if !from.get_neighbours().contains(&to) {
+            return Err(RulesError::NotNeighbours(from, to));
+        }
+        if self.workers[to].is_some() {
+            return Err(RulesError::Occupied(to));
+        }
+        if self.blocks[to] == T4 {
+            return Err(RulesError::Dome(to));
+        }
+        if !self.blocks[from].is_reachable(&self.blocks[to]) {
+            return Err(RulesError::TooHigh(from, to));
+        }
+
+        if self.blocks[to] == T3 {
+            return match at {
+                Some(at) => Err(RulesError::BuildOnWin(at)),
+                None => Ok(()),
+            };
+        }
+
+        let at = at.ok_or(RulesError::MissingBuild(to))?;
+        if !to.get_neighbours().contains(&at) {
+            return Err(RulesError::NotNeighbours(to, at));
+        }
+        if self.workers[at].is_some() && at != from {
+            return Err(RulesError::Occupied(at));
+        }
+        if self.blocks[at] == T4 {
+            return Err(RulesError::Dome(at));
+        }
+
+        Ok(())
+    }
+    fn build(&mut self, at: Square) {
         self.blocks[at] = match self.blocks[at] {
             T0 => T1,
             T1 => T2,
             T2 => T3,
             T3 => T4,
-            T4 => panic!("Can't build over 4 blocks at {}!", at),
+            T4 => unreachable!("Builds on domes are rejected by check_move"),
         };
     }
     fn check_normal_victory(&mut self) {
@@ -175,13 +428,6 @@ impl Board {
         self.victory = Some(self.turn.next());
     }
     fn move_worker(&mut self, from: Square, to: Square) {
-        assert!(self.workers[to].is_none(), "Can't move over another worker in {}!", to);
-        assert!(self.workers[from].is_some(), "Can't move because there's no worker in {}!", from);
-        assert!(from.get_neighbours().contains(&to),
-            "Can't move from {} to {} because they aren't neighbours!", from, to);
-        assert!(self.blocks[from].is_reachable(&self.blocks[to]),
-            "Can't move from {} to {} because it's too high!", from, to);
-
         self.workers[to] = self.workers[from];
         self.workers[from] = None;
     }
@@ -190,19 +436,428 @@ impl Board {
     }
 }
 
-#[derive(Debug)]
+impl FromStr for Board {
+    type Err = String;
+
+    // Finished positions are accepted, with the victory set as if the game had reached them
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let fields: Vec<&str> = s.split_whitespace().collect();
+        let [heights, workers_p1, workers_p2, turn] = fields[..] else {
+            return Err(format!("\"{}\" should have four fields: heights, P1's workers, P2's workers and the side to move!", s));
+        };
+
+        let rows: Vec<&str> = heights.split('/').collect();
+        if rows.len() != 5 {
+            return Err(format!("{} should have five rows of heights separated by /!", heights));
+        }
+        let mut blocks = [T0 ; 25];
+        for (row, text) in rows.iter().enumerate() {
+            if text.chars().count() != 5 {
+                return Err(format!("Row {} of the heights should have five squares, got {}!", row + 1, text));
+            }
+            for (column, height) in text.chars().enumerate() {
+                let height = height.to_digit(10)
+                    .ok_or(format!("{} is not a valid height! Heights go from 0 to 4.", height))?;
+                blocks[row * 5 + column] = Blocks::try_from(height as usize)?;
+            }
+        }
+
+        let mut workers: [Option<Worker> ; 25] = Default::default();
+        for (turn, text) in [(P1, workers_p1), (P2, workers_p2)] {
+            if text == "-" {
+                continue;
+            }
+            let squares = text.split(',').map(Square::from_str).collect::<Result<Vec<Square>, String>>()?;
+            if squares.len() != 2 {
+                return Err(format!("{} should place two workers, got {}!", turn, text));
+            }
+            for square in squares {
+                if workers[square].is_some() {
+                    return Err(RulesError::Occupied(square).to_string());
+                }
+                if blocks[square] == T4 {
+                    return Err(RulesError::Dome(square).to_string());
+                }
+                workers[square] = Some(Worker { turn });
+            }
+        }
+
+        let mut board = Self {
+            blocks,
+            workers,
+            turn: turn.parse()?,
+            victory: None,
+        };
+
+        if board.in_setup() {
+            let placed = board.workers.iter().filter(|x| x.is_some()).count();
+            let expected = if placed == 0 { P1 } else { P2 };
+            if workers_p1 == "-" && workers_p2 != "-" {
+                return Err(String::from("P2 can't place workers before P1!"));
+            }
+            if board.turn != expected {
+                return Err(format!("It should be {}'s turn to place workers!", expected));
+            }
+            if let Some(square) = Square::squares().into_iter().find(|&x| board.workers[x].is_some() && blocks[x] == T3) {
+                return Err(RulesError::ThirdLevel(square).to_string());
+            }
+        } else {
+            board.check_normal_victory();
+            if board.victory.is_none() {
+                board.check_smother_victory();
+            }
+        }
+
+        Ok(board)
+    }
+}
+
+// Why a position, placement or move breaks the rules
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum RulesError {
+    // Moves are only allowed once every worker is placed
+    InSetup,
+    // Placements are only allowed before every worker is placed
+    SetupOver,
+    GameOver,
+    AlreadyWon(Turn),
+    NoWorker(Square),
+    // The worker belongs to the side not to move
+    WrongWorker(Square),
+    NotNeighbours(Square, Square),
+    Occupied(Square),
+    Dome(Square),
+    // The first square's level is too low to climb to the second
+    TooHigh(Square, Square),
+    // Workers can't be placed on the third level, since they would have already won
+    ThirdLevel(Square),
+    MissingBuild(Square),
+    // Moves onto the third level win straight away, so they don't build
+    BuildOnWin(Square),
+}
+impl Display for RulesError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RulesError::InSetup => write!(f, "Can't move before all workers are placed!"),
+            RulesError::SetupOver => write!(f, "Can't place workers after the setup phase!"),
+            RulesError::GameOver => write!(f, "Can't move after the game is over!"),
+            RulesError::AlreadyWon(turn) => write!(f, "Can't start from a position {} already won!", turn),
+            RulesError::NoWorker(square) => write!(f, "There's no worker in {}!", square),
+            RulesError::WrongWorker(square) => write!(f, "The worker in {} belongs to the other player!", square),
+            RulesError::NotNeighbours(a, b) => write!(f, "{} and {} aren't neighbours!", a, b),
+            RulesError::Occupied(square) => write!(f, "There's already a worker in {}!", square),
+            RulesError::Dome(square) => write!(f, "There's a dome in {}!", square),
+            RulesError::TooHigh(from, to) => write!(f, "Can't climb from {} to {} because it's too high!", from, to),
+            RulesError::ThirdLevel(square) => write!(f, "Can't place a worker on the third level in {}!", square),
+            RulesError::MissingBuild(to) => write!(f, "Can't move to {} without building!", to),
+            RulesError::BuildOnWin(at) => write!(f, "Can't build at {} after a winning move!", at),
+        }
+    }
+}
+impl std::error::Error for RulesError {}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
 pub struct Move {
     pub from: Square,
     pub to: Square,
     pub at: Option<Square>,
 }
 
+// Written as from-to^build, e.g. "C3-D4^D5", or just "C3-D4" for a winning move
+impl Display for Move {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.at {
+            Some(at) => write!(f, "{}-{}^{}", self.from, self.to, at),
+            None => write!(f, "{}-{}", self.from, self.to),
+        }
+    }
+}
+impl FromStr for Move {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (from, rest) = s.split_once('-')
+            .ok_or(format!("{} is not a valid move! Moves look like C3-D4^D5.", s))?;
+        let (to, at) = match rest.split_once('^') {
+            Some((to, at)) => (to, Some(at.parse()?)),
+            None => (rest, None),
+        };
+
+        Ok(Move {
+            from: from.parse()?,
+            to: to.parse()?,
+            at,
+        })
+    }
+}
+
+// One turn of a game: either the placement of both workers during setup or a move
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Ply {
+    Place([Square ; 2]),
+    Move(Move),
+}
+impl Display for Ply {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Ply::Place(squares) => write!(f, "{},{}", squares[0], squares[1]),
+            Ply::Move(mv) => write!(f, "{}", mv),
+        }
+    }
+}
+
+impl FromStr for Ply {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.split_once(',') {
+            Some((first, second)) => Ok(Ply::Place([first.parse()?, second.parse()?])),
+            None => Ok(Ply::Move(s.parse()?)),
+        }
+    }
+}
+
+// What the player reported about a ply, when known
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct PlyInfo {
+    pub time_spent: Option<Duration>,
+    pub eval: Option<Score>,
+}
+
+// A game with its history on top of Board. Undone plies are kept so they can be redone, until
+// a different ply is played in their place.
+#[derive(Clone, Debug)]
+pub struct GameState {
+    // positions[i] is the board after i plies
+    positions: Vec<Board>,
+    plies: Vec<(Ply, PlyInfo)>,
+    current: usize,
+}
+impl GameState {
+    pub fn new(start: Board) -> Self {
+        Self {
+            positions: vec![start],
+            plies: vec![],
+            current: 0,
+        }
+    }
+    pub fn get_board(&self) -> &Board {
+        &self.positions[self.current]
+    }
+    pub fn get_start(&self) -> &Board {
+        &self.positions[0]
+    }
+    // How many plies were played to reach the current board
+    pub fn get_ply(&self) -> usize {
+        self.current
+    }
+    // Every known ply, including the ones that can be redone
+    pub fn get_plies(&self) -> &[(Ply, PlyInfo)] {
+        &self.plies
+    }
+    pub fn get_placements(&self) -> Vec<[Square ; 2]> {
+        self.plies[..self.current]
+            .iter()
+            .filter_map(|(ply, _)| match ply {
+                Ply::Place(squares) => Some(*squares),
+                Ply::Move(_) => None,
+            })
+            .collect()
+    }
+    pub fn get_moves(&self) -> Vec<Move> {
+        self.plies[..self.current]
+            .iter()
+            .filter_map(|(ply, _)| match ply {
+                Ply::Move(mv) => Some(*mv),
+                Ply::Place(_) => None,
+            })
+            .collect()
+    }
+
+    pub fn play(&mut self, ply: Ply, info: PlyInfo) -> Result<(), RulesError> {
+        let mut board = *self.get_board();
+        match ply {
+            Ply::Place(squares) => board.try_place_workers(squares)?,
+            Ply::Move(mv) => board.try_apply_move(mv)?,
+        }
+
+        self.positions.truncate(self.current + 1);
+        self.plies.truncate(self.current);
+        self.positions.push(board);
+        self.plies.push((ply, info));
+        self.current += 1;
+        Ok(())
+    }
+    // Returns the ply taken back, if there was one
+    pub fn undo(&mut self) -> Option<Ply> {
+        if self.current == 0 {
+            return None;
+        }
+        self.current -= 1;
+        Some(self.plies[self.current].0)
+    }
+    pub fn redo(&mut self) -> Option<Ply> {
+        if self.current == self.plies.len() {
+            return None;
+        }
+        self.current += 1;
+        Some(self.plies[self.current - 1].0)
+    }
+    pub fn jump_to(&mut self, ply: usize) -> Result<(), String> {
+        if ply > self.plies.len() {
+            return Err(format!("Can't jump to ply {}, the game only has {}!", ply, self.plies.len()));
+        }
+        self.current = ply;
+        Ok(())
+    }
+}
+
+// What ends a search. Limits combine and the search stops at the first one reached, so a search
+// with none of them goes as deep as the engine allows. multi_pv isn't a limit but rides along
+// since it is asked for with the same go command.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct SearchLimits {
+    // Clock of each player
+    pub time: [Option<Duration> ; 2],
+    // Added to a player's clock after each of their moves
+    pub increment: [Duration ; 2],
+    // Moves the side to move has left before its clock gets more time, None if it never does
+    pub moves_to_go: Option<usize>,
+    pub movetime: Option<Duration>,
+    pub depth: Option<usize>,
+    pub nodes: Option<usize>,
+    // Look for a win in at most this many moves
+    pub mate: Option<usize>,
+    // Search until told to stop
+    pub infinite: bool,
+    // How many of the best root moves get their own score and variation, only the best if None
+    pub multi_pv: Option<usize>,
+}
+impl SearchLimits {
+    // Sudden death, where only the clock of the side to move is known
+    pub fn clock(turn: Turn, time_left: Duration) -> Self {
+        let mut limits = SearchLimits::default();
+        limits.time[turn] = Some(time_left);
+        limits
+    }
+    pub fn depth(depth: usize) -> Self {
+        SearchLimits {
+            depth: Some(depth),
+            ..Default::default()
+        }
+    }
+    pub fn movetime(movetime: Duration) -> Self {
+        SearchLimits {
+            movetime: Some(movetime),
+            ..Default::default()
+        }
+    }
+
+    // The depth in plies the search may not go past, a win in N moves being N moves of the side
+    // to search and N - 1 replies. It is at least 1, as the engines always finish their first
+    // iteration.
+    pub fn get_max_depth(&self) -> Option<usize> {
+        let mate_depth = self.mate.map(|moves| (2 * moves).saturating_sub(1));
+        let max_depth = match (self.depth, mate_depth) {
+            (Some(depth), Some(mate_depth)) => Some(depth.min(mate_depth)),
+            (depth, mate_depth) => depth.or(mate_depth),
+        };
+        max_depth.map(|x| x.max(1))
+    }
+}
+
+// Shared between a running search and whoever started it, so the search can be stopped from
+// another thread and its progress followed
+#[derive(Debug, Default)]
+pub struct SearchControl {
+    stop: AtomicBool,
+    nodes: AtomicUsize,
+}
+impl SearchControl {
+    // The search answers as soon as it notices, with the best move found so far
+    pub fn stop(&self) {
+        self.stop.store(true, Ordering::Relaxed);
+    }
+    pub fn is_stopped(&self) -> bool {
+        self.stop.load(Ordering::Relaxed)
+    }
+    // Nodes searched so far, updated by the engine every few thousand nodes
+    pub fn get_nodes(&self) -> usize {
+        self.nodes.load(Ordering::Relaxed)
+    }
+    pub fn set_nodes(&self, nodes: usize) {
+        self.nodes.store(nodes, Ordering::Relaxed);
+    }
+}
+
+// What a search has found after one iteration of iterative deepening
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SearchInfo {
+    pub depth: usize,
+    // The deepest ply any line reached
+    pub seldepth: usize,
+    pub score: Score,
+    pub nodes: usize,
+    pub nps: usize,
+    pub elapsed: Duration,
+    pub pv: Vec<Move>,
+    // Permille of the transposition table in use by this search, for engines that have one
+    pub hashfull: Option<usize>,
+}
+impl SearchInfo {
+    pub fn get_nps(nodes: usize, elapsed: Duration) -> usize {
+        (nodes as f64 / elapsed.as_secs_f64().max(1e-6)) as usize
+    }
+}
+
+// Where engines send a SearchInfo after every iteration. It's called on the search thread, so
+// it should be quick.
+#[derive(Clone)]
+pub struct InfoSink(Arc<dyn Fn(&SearchInfo) + Send + Sync>);
+impl InfoSink {
+    pub fn new(sink: impl Fn(&SearchInfo) + Send + Sync + 'static) -> Self {
+        InfoSink(Arc::new(sink))
+    }
+    pub fn send(&self, info: &SearchInfo) {
+        (self.0)(info)
+    }
+}
+impl std::fmt::Debug for InfoSink {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "InfoSink")
+    }
+}
+
 #[derive(Debug)]
 pub struct Request<'a> {
     pub blocks: &'a [Blocks ; 25],
     pub workers: &'a [Option<Worker> ; 25],
     pub turn: &'a Turn,
-    pub time_left: Duration,
+    pub limits: SearchLimits,
+    pub control: Arc<SearchControl>,
+    pub sink: Option<InfoSink>,
+}
+impl Request<'_> {
+    // The clock of the side to move, if the request has one
+    pub fn get_time_left(&self) -> Option<Duration> {
+        self.limits.time[*self.turn]
+    }
+    // The board the request was made from
+    pub fn get_board(&self) -> Board {
+        let mut board = Board {
+            blocks: *self.blocks,
+            workers: *self.workers,
+            turn: *self.turn,
+            victory: None,
+        };
+        if !board.in_setup() {
+            board.check_normal_victory();
+            if board.victory.is_none() {
+                board.check_smother_victory();
+            }
+        }
+        board
+    }
 }
 
 #[derive(Clone, Copy, Debug, Eq, PartialEq)]
@@ -257,7 +912,7 @@ impl Square {
             D4 => vec![C3, C4, C5, D3, D5, E3, E4, E5],
             D5 => vec![C4, C5, D4, E4, E5],
             E1 => vec![D1, D2, E2],
-            E2 => vec![D1, D2, D3, E1, E2],
+            E2 => vec![D1, D2, D3, E1, E3],
             E3 => vec![D2, D3, D4, E2, E4],
             E4 => vec![D3, D4, D5, E3, E5],
             E5 => vec![D4, D5, E4],
@@ -304,6 +959,16 @@ impl Display for Square {
         })
     }
 }
+impl FromStr for Square {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Square::squares()
+            .into_iter()
+            .find(|square| square.to_string().eq_ignore_ascii_case(s))
+            .ok_or(format!("{} is not a valid square! Squares go from A1 to E5.", s))
+    }
+}
 impl<T> Index<Square> for [T] {
     type Output = T;
 
@@ -328,9 +993,9 @@ impl<T> IndexMut<Square> for Vec<T> {
         &mut self[Into::<usize>::into(index)]
     }
 }
-impl Into<usize> for Square {
-    fn into(self) -> usize {
-        match self {
+impl From<Square> for usize {
+    fn from(value: Square) -> Self {
+        match value {
             Square::A1 => 0,
             Square::A2 => 1,
             Square::A3 => 2,
@@ -420,6 +1085,17 @@ impl Display for Turn {
         })
     }
 }
+impl FromStr for Turn {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "P1" | "p1" => Ok(P1),
+            "P2" | "p2" => Ok(P2),
+            _ => Err(format!("{} is not a valid turn! Turns are P1 and P2.", s)),
+        }
+    }
+}
 impl<T> Index<Turn> for [T] {
     type Output = T;
 
@@ -444,9 +1120,9 @@ impl<T> IndexMut<Turn> for Vec<T> {
         &mut self[Into::<usize>::into(index)]
     }
 }
-impl Into<usize> for Turn {
-    fn into(self) -> usize {
-        match self {
+impl From<Turn> for usize {
+    fn from(value: Turn) -> Self {
+        match value {
             Turn::P1 => 0,
             Turn::P2 => 1,
         }
@@ -483,17 +1159,370 @@ pub struct EngineInfo {
     pub eval_range: (isize, isize),
 }
 
+// How good a position is for the side to move: either the engine's evaluation, within its
+// EngineInfo::eval_range, or a forced end of the game some number of plies away
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum Score {
+    Eval(isize),
+    Win(usize),
+    Loss(usize),
+}
+impl Score {
+    // Searches work with plain numbers, where a win n plies away is worth WIN_VALUE - n so that
+    // quicker wins are worth more. Evaluations have to stay below WIN_VALUE - MAX_PLIES.
+    pub const WIN_VALUE: isize = 10000;
+    const MAX_PLIES: isize = 1000;
+
+    pub fn from_value(value: isize) -> Self {
+        if value >= Score::WIN_VALUE - Score::MAX_PLIES {
+            Score::Win((Score::WIN_VALUE - value).max(0) as usize)
+        } else if value <= -(Score::WIN_VALUE - Score::MAX_PLIES) {
+            Score::Loss((Score::WIN_VALUE + value).max(0) as usize)
+        } else {
+            Score::Eval(value)
+        }
+    }
+    pub fn to_value(self) -> isize {
+        match self {
+            Score::Eval(value) => value,
+            Score::Win(plies) => Score::WIN_VALUE - plies as isize,
+            Score::Loss(plies) => -(Score::WIN_VALUE - plies as isize),
+        }
+    }
+}
+impl Ord for Score {
+    fn cmp(&self, other: &Self) -> Order {
+        self.to_value().cmp(&other.to_value())
+    }
+}
+impl PartialOrd for Score {
+    fn partial_cmp(&self, other: &Self) -> Option<Order> {
+        Some(self.cmp(other))
+    }
+}
+// The same position seen from the other side
+impl Neg for Score {
+    type Output = Score;
+
+    fn neg(self) -> Self::Output {
+        match self {
+            Score::Eval(value) => Score::Eval(-value),
+            Score::Win(plies) => Score::Loss(plies),
+            Score::Loss(plies) => Score::Win(plies),
+        }
+    }
+}
+// "12" or "-3" for evaluations, "win 3" and "loss 2" for the plies left until the game ends
+impl FromStr for Score {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let parse_plies = |plies: &str| plies.parse().map_err(|_| format!("{} is not a valid number of plies", plies));
+        match s.split_once(' ') {
+            Some(("win", plies)) => Ok(Score::Win(parse_plies(plies)?)),
+            Some(("loss", plies)) => Ok(Score::Loss(parse_plies(plies)?)),
+            Some(_) => Err(format!("{} is not a valid score", s)),
+            None => s.parse().map(Score::Eval).map_err(|_| format!("{} is not a valid score", s)),
+        }
+    }
+}
+impl Display for Score {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Score::Eval(value) => write!(f, "{}", value),
+            Score::Win(plies) => write!(f, "win {}", plies),
+            Score::Loss(plies) => write!(f, "loss {}", plies),
+        }
+    }
+}
+
 #[derive(Debug)]
 pub struct SearchResult{
     pub mv: Move,
-    pub eval: Option<isize>,
+    pub eval: Option<Score>,
     pub pv: Option<Vec<Move>>,
     pub depth_searched: Option<usize>,
     pub time_spent: Option<Duration>,
+    // The best root moves from best to worst, as many as SearchLimits::multi_pv asked for, or
+    // empty for engines that only know their best move
+    pub root_moves: Vec<RootMoveResult>,
 }
 
-pub struct BenchmarkRequest{
-    pub position: Board,
-    pub max_depth: usize,
-    pub debug: bool,
-}
\ No newline at end of file
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct RootMoveResult {
+    pub mv: Move,
+    pub eval: Score,
+    // Starts with mv
+    pub pv: Vec<Move>,
+}
+
+#[derive(Debug)]
+pub struct PlacementResult {
+    pub squares: [Square ; 2],
+    pub eval: Option<Score>,
+    pub time_spent: Option<Duration>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn notation() {
+        let board = Board::new([B3, C2], [C4, D3]);
+        assert_eq!(board.to_notation(), "00000/00000/00000/00000/00000 B3,C2 C4,D3 P1");
+        assert_eq!(board.to_notation().parse::<Board>(), Ok(board));
+
+        let mut blocks = [T0 ; 25];
+        blocks[B3] = T1;
+        blocks[E5] = T4;
+        let board = Board::new_custom(blocks, [B2, B4], [D2, D4], P2);
+        assert_eq!(board.to_notation(), "00000/00100/00000/00000/00004 B2,B4 D2,D4 P2");
+        assert_eq!(board.to_notation().parse::<Board>(), Ok(board));
+    }
+
+    #[test]
+    fn setup_notation() {
+        let mut board = Board::empty();
+        assert_eq!(board.to_notation(), "00000/00000/00000/00000/00000 - - P1");
+        assert_eq!(board.to_notation().parse::<Board>(), Ok(board));
+
+        board.place_workers([C3, B3]);
+        assert_eq!(board.to_notation(), "00000/00000/00000/00000/00000 B3,C3 - P2");
+        assert_eq!(board.to_notation().parse::<Board>(), Ok(board));
+    }
+
+    #[test]
+    fn finished_notation() {
+        let board: Board = "00000/00000/00300/00000/00000 C3,A1 E4,E5 P2".parse().unwrap();
+        assert_eq!(board.get_victory(), Some(P1));
+    }
+
+    #[test]
+    fn legal_moves() {
+        // Same position as the opening_position tests of the engines' move generators
+        let board = Board::new([B3, C3], [C2, C4]);
+        let moves = board.legal_moves();
+        assert_eq!(moves.len(), 59);
+        assert!(moves.iter().all(|mv| board.is_legal(mv)));
+        assert_eq!(board.legal_moves_from(B3).len() + board.legal_moves_from(C3).len(), 59);
+        assert!(board.legal_moves_from(C2).is_empty());
+
+        assert!(!board.is_legal(&Move { from: B3, to: C2, at: Some(B3) }));
+        assert!(!board.is_legal(&Move { from: B3, to: A3, at: None }));
+        assert!(!board.is_legal(&Move { from: B3, to: A3, at: Some(C2) }));
+        assert!(board.is_legal(&Move { from: B3, to: A3, at: Some(B3) }));
+    }
+
+    #[test]
+    fn per_worker_queries() {
+        let mut blocks = [T0 ; 25];
+        blocks[A2] = T2;
+        blocks[B1] = T4;
+        blocks[B2] = T1;
+        let board = Board::new_custom(blocks, [A1, E5], [C3, D4], P1);
+
+        assert_eq!(board.legal_destinations(A1), vec![B2]);
+        assert_eq!(board.legal_builds(A1, B2), vec![A1, A2, A3, B3, C1, C2]);
+        assert!(board.legal_builds(A1, A2).is_empty());
+        assert!(board.legal_destinations(C3).is_empty());
+    }
+
+    #[test]
+    fn winning_moves() {
+        let mut blocks = [T0 ; 25];
+        blocks[B2] = T2;
+        blocks[A1] = T3;
+        let board = Board::new_custom(blocks, [B2, E5], [C4, D3], P1);
+
+        assert!(board.is_legal(&Move { from: B2, to: A1, at: None }));
+        assert!(!board.is_legal(&Move { from: B2, to: A1, at: Some(A2) }));
+        assert!(board.legal_builds(B2, A1).is_empty());
+
+        let mut after = board;
+        after.apply_move(Move { from: B2, to: A1, at: None });
+        assert!(after.legal_moves().is_empty());
+    }
+
+    #[test]
+    fn rules_errors() {
+        let mut blocks = [T0 ; 25];
+        blocks[A2] = T2;
+        blocks[B1] = T4;
+        blocks[B3] = T3;
+        let mut board = Board::new_custom(blocks, [A1, C3], [C4, D3], P1);
+        let before = board;
+
+        let errors = [
+            (Move { from: E5, to: E4, at: Some(E3) }, RulesError::NoWorker(E5)),
+            (Move { from: C4, to: C5, at: Some(B5) }, RulesError::WrongWorker(C4)),
+            (Move { from: A1, to: A3, at: Some(A4) }, RulesError::NotNeighbours(A1, A3)),
+            (Move { from: C3, to: D3, at: Some(E3) }, RulesError::Occupied(D3)),
+            (Move { from: A1, to: B1, at: Some(C1) }, RulesError::Dome(B1)),
+            (Move { from: A1, to: A2, at: Some(A3) }, RulesError::TooHigh(A1, A2)),
+            (Move { from: A1, to: B2, at: None }, RulesError::MissingBuild(B2)),
+            (Move { from: A1, to: B2, at: Some(D2) }, RulesError::NotNeighbours(B2, D2)),
+            (Move { from: A1, to: B2, at: Some(C3) }, RulesError::Occupied(C3)),
+            (Move { from: A1, to: B2, at: Some(B1) }, RulesError::Dome(B1)),
+        ];
+        for (mv, error) in errors {
+            assert_eq!(board.try_apply_move(mv), Err(error), "{}", mv);
+            assert_eq!(board, before);
+        }
+
+        assert_eq!(board.try_apply_move(Move { from: A1, to: B2, at: Some(A1) }), Ok(()));
+        assert_eq!(board.try_apply_move(Move { from: C4, to: C5, at: None }), Err(RulesError::MissingBuild(C5)));
+    }
+
+    #[test]
+    fn rules_errors_around_the_game() {
+        let mut blocks = [T0 ; 25];
+        blocks[B2] = T2;
+        blocks[A1] = T3;
+        let mut board = Board::new_custom(blocks, [B2, E5], [C4, D3], P1);
+        assert_eq!(board.try_apply_move(Move { from: B2, to: A1, at: Some(A2) }), Err(RulesError::BuildOnWin(A2)));
+        board.apply_move(Move { from: B2, to: A1, at: None });
+        assert_eq!(board.try_apply_move(Move { from: C4, to: C5, at: Some(B5) }), Err(RulesError::GameOver));
+
+        assert_eq!(Board::try_new_custom(blocks, [A1, E5], [C4, D3], P2), Err(RulesError::AlreadyWon(P1)));
+        assert_eq!(Board::try_new_custom(blocks, [B2, E5], [C4, B2], P1), Err(RulesError::Occupied(B2)));
+        blocks[A1] = T4;
+        assert_eq!(Board::try_new_custom(blocks, [A1, E5], [C4, D3], P1), Err(RulesError::Dome(A1)));
+
+        let mut board = Board::empty();
+        assert_eq!(board.try_apply_move(Move { from: C3, to: C4, at: Some(C5) }), Err(RulesError::InSetup));
+        assert_eq!(board.try_place_workers([C3, C3]), Err(RulesError::Occupied(C3)));
+        board.place_workers([C3, B3]);
+        assert_eq!(board.try_place_workers([C3, D3]), Err(RulesError::Occupied(C3)));
+        board.place_workers([C4, D3]);
+        assert_eq!(board.try_place_workers([A1, A2]), Err(RulesError::SetupOver));
+    }
+
+    #[test]
+    fn game_state_history() {
+        let mut game = GameState::new(Board::empty());
+        let info = PlyInfo { time_spent: Some(Duration::from_millis(5)), eval: Some(Score::Eval(3)) };
+        game.play(Ply::Place([C3, B3]), info).unwrap();
+        game.play(Ply::Place([C2, D4]), PlyInfo::default()).unwrap();
+        game.play(Ply::Move(Move { from: B3, to: B4, at: Some(B5) }), PlyInfo::default()).unwrap();
+        let after_move = *game.get_board();
+
+        assert_eq!(game.play(Ply::Place([A1, A2]), PlyInfo::default()), Err(RulesError::SetupOver));
+        assert_eq!(game.get_ply(), 3);
+
+        assert_eq!(game.undo(), Some(Ply::Move(Move { from: B3, to: B4, at: Some(B5) })));
+        assert_eq!(game.get_board().get_blocks()[B5], T0);
+        assert_eq!(game.get_moves(), vec![]);
+        assert_eq!(game.redo(), Some(Ply::Move(Move { from: B3, to: B4, at: Some(B5) })));
+        assert_eq!(*game.get_board(), after_move);
+        assert_eq!(game.redo(), None);
+
+        game.jump_to(0).unwrap();
+        assert_eq!(game.get_board(), &Board::empty());
+        assert_eq!(game.undo(), None);
+        assert!(game.jump_to(4).is_err());
+        game.jump_to(1).unwrap();
+        assert_eq!(game.get_placements(), vec![[C3, B3]]);
+        assert_eq!(game.get_plies()[0].1, info);
+    }
+
+    #[test]
+    fn game_state_branches() {
+        let mut game = GameState::new(Board::new([B3, C2], [C4, D3]));
+        game.play(Ply::Move(Move { from: B3, to: A3, at: Some(A4) }), PlyInfo::default()).unwrap();
+        game.play(Ply::Move(Move { from: C4, to: C5, at: Some(B5) }), PlyInfo::default()).unwrap();
+        game.undo();
+        game.undo();
+
+        game.play(Ply::Move(Move { from: C2, to: B2, at: Some(A1) }), PlyInfo::default()).unwrap();
+        assert_eq!(game.get_plies().len(), 1);
+        assert_eq!(game.redo(), None);
+        assert_eq!(game.get_board().get_blocks()[A4], T0);
+        assert_eq!(game.get_board().get_blocks()[A1], T1);
+    }
+
+    #[test]
+    fn move_notation() {
+        let mv = Move { from: C3, to: D4, at: Some(D5) };
+        assert_eq!(mv.to_string(), "C3-D4^D5");
+        assert_eq!("c3-d4^d5".parse::<Move>(), Ok(mv));
+
+        let winning = Move { from: C3, to: D4, at: None };
+        assert_eq!(winning.to_string(), "C3-D4");
+        assert_eq!("C3-D4".parse::<Move>(), Ok(winning));
+
+        assert!("C3D4".parse::<Move>().is_err());
+        assert!("C3-F4^D5".parse::<Move>().is_err());
+        assert!("C3-D4^".parse::<Move>().is_err());
+    }
+
+    #[test]
+    fn parse_move_against_board() {
+        let board = Board::new([B3, C2], [C4, D3]);
+        assert_eq!(board.parse_move("B3-A3^A4"), Ok(Move { from: B3, to: A3, at: Some(A4) }));
+        assert_eq!(board.parse_move("a3^a4"), Ok(Move { from: B3, to: A3, at: Some(A4) }));
+        // Both workers can reach B2
+        assert!(board.parse_move("B2^A1").unwrap_err().contains("ambiguous"));
+        assert_eq!(board.parse_move("C2-B2^A1"), Ok(Move { from: C2, to: B2, at: Some(A1) }));
+        // Moving onto an opponent's worker
+        assert!(board.parse_move("C4^C5").is_err());
+
+        let mut blocks = [T0 ; 25];
+        blocks[B2] = T2;
+        blocks[A1] = T3;
+        let board = Board::new_custom(blocks, [B2, E5], [C4, D3], P1);
+        assert_eq!(board.parse_move("A1"), Ok(Move { from: B2, to: A1, at: None }));
+    }
+
+    #[test]
+    fn notation_errors() {
+        let errors = [
+            "00000/00000/00000/00000 B3,C2 C4,D3 P1",
+            "00000/00000/00000/00000/0000 B3,C2 C4,D3 P1",
+            "00000/00000/00000/00000/00005 B3,C2 C4,D3 P1",
+            "00000/00000/00000/00000/0000x B3,C2 C4,D3 P1",
+            "00000/00000/00000/00000/00000 B3,C2 C4,F3 P1",
+            "00000/00000/00000/00000/00000 B3,C2 C4 P1",
+            "00000/00000/00000/00000/00000 B3,C2 C4,C2 P1",
+            "00000/00000/00000/00000/00004 B3,C2 C4,E5 P1",
+            "00000/00000/00000/00000/00000 B3,C2 C4,D3 P3",
+            "00000/00000/00000/00000/00000 B3,C2 C4,D3",
+            "00000/00000/00000/00000/00000 - C4,D3 P2",
+            "00000/00000/00000/00000/00000 B3,C2 - P1",
+        ];
+        for notation in errors {
+            assert!(notation.parse::<Board>().is_err(), "{} should not parse", notation);
+        }
+    }
+
+    #[test]
+    fn search_limits() {
+        assert_eq!(SearchLimits::default().get_max_depth(), None);
+        assert_eq!(SearchLimits::depth(6).get_max_depth(), Some(6));
+        assert_eq!(SearchLimits { mate: Some(2), ..Default::default() }.get_max_depth(), Some(3));
+        assert_eq!(SearchLimits { mate: Some(3), ..SearchLimits::depth(4) }.get_max_depth(), Some(4));
+        assert_eq!(SearchLimits::depth(0).get_max_depth(), Some(1));
+        assert_eq!(SearchLimits { mate: Some(0), ..Default::default() }.get_max_depth(), Some(1));
+
+        let board = Board::new([B3, C2], [C4, D3]);
+        let request = board.get_request(SearchLimits::clock(P1, Duration::from_secs(5)));
+        assert_eq!(request.get_time_left(), Some(Duration::from_secs(5)));
+        assert_eq!(request.limits.time[P2], None);
+    }
+
+    #[test]
+    fn scores() {
+        let mut scores = vec![Score::Win(3), Score::Eval(-5), Score::Loss(2), Score::Win(1), Score::Eval(12), Score::Loss(6)];
+        scores.sort();
+        assert_eq!(scores, vec![Score::Loss(2), Score::Loss(6), Score::Eval(-5), Score::Eval(12), Score::Win(3), Score::Win(1)]);
+
+        for score in scores {
+            assert_eq!(Score::from_value(score.to_value()), score);
+            assert_eq!(Score::from_value(-score.to_value()), -score);
+            assert_eq!(score.to_string().parse(), Ok(score));
+        }
+        assert_eq!(Score::Win(3).to_string(), "win 3");
+        assert!("win".parse::<Score>().is_err());
+        assert!("draw 2".parse::<Score>().is_err());
+    }
+}
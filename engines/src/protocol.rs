@@ -0,0 +1,432 @@
+// The Santorini Engine Interface, a line based text protocol in the spirit of UCI. The client
+// writes commands to the engine's stdin and reads responses from its stdout.
+//
+//     client: sei
+//     engine: id name spectre
+//     engine: id evalrange -46 46
+//     engine: option name Hash
+//     engine: seiok
+//     client: position 00000/00000/00000/00000/00000 - - P1 moves C3,B3 C2,D4
+//     client: go p1time 60000 p2time 60000
+//     engine: info depth 5 seldepth 5 score 1 nodes 20480 nps 51200 time 400 pv B3-B4^B5 D4-D3^D2
+//     engine: info depth 6 seldepth 6 score win 5 nodes 41960 nps 51667 time 812 pv B3-B4^B5 D4-D3^D2
+//     engine: bestmove B3-B4^B5
+//     client: quit
+//
+// Times are in milliseconds. Positions use the board notation of Board::to_notation, followed
+// by plies in the notation of Ply. Scores are the engine's evaluation, or "win N" and "loss N"
+// when the game ends in N plies. During setup, bestmove answers with a placement, and once the
+// game is over with "bestmove none". An engine that fails during a search answers with
+// "error MESSAGE" instead of bestmove. Option names are matched without regard to case.
+
+use std::{fmt::Display, str::FromStr, time::Duration};
+
+use crate::models::*;
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Command {
+    Sei,
+    IsReady,
+    NewGame,
+    SetOption { name: String, value: String },
+    Position { board: Board, plies: Vec<Ply> },
+    Go(SearchLimits),
+    Stop,
+    Quit,
+}
+
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Info {
+    // Which of the best root moves the line is about, 1 being the best, when several are asked for
+    pub multipv: Option<usize>,
+    pub depth: Option<usize>,
+    pub seldepth: Option<usize>,
+    pub score: Option<Score>,
+    pub nodes: Option<usize>,
+    pub nps: Option<usize>,
+    // Permille of the transposition table in use
+    pub hashfull: Option<usize>,
+    pub time: Option<Duration>,
+    pub pv: Option<Vec<Move>>,
+}
+impl From<&SearchInfo> for Info {
+    fn from(info: &SearchInfo) -> Self {
+        Info {
+            multipv: None,
+            depth: Some(info.depth),
+            seldepth: Some(info.seldepth),
+            score: Some(info.score),
+            nodes: Some(info.nodes),
+            nps: Some(info.nps),
+            hashfull: info.hashfull,
+            time: Some(info.elapsed),
+            pv: Some(info.pv.clone()),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Response {
+    IdName(String),
+    IdEvalRange(isize, isize),
+    // An option the engine accepts in setoption
+    Option(String),
+    SeiOk,
+    ReadyOk,
+    Info(Info),
+    // Free text for humans reading the log, such as warnings
+    InfoString(String),
+    BestMove(Option<Ply>),
+    // Ends a search the engine failed at, in place of bestmove
+    Error(String),
+}
+
+fn parse_value<T: FromStr>(name: &str, value: Option<&str>) -> Result<T, String> {
+    let value = value.ok_or(format!("Missing value for {}!", name))?;
+    value.parse().map_err(|_| format!("{} is not a valid value for {}!", value, name))
+}
+
+// Scores take a second token when they are a win or a loss
+fn parse_score<'a>(name: &str, tokens: &mut impl Iterator<Item = &'a str>) -> Result<Score, String> {
+    match tokens.next() {
+        Some(kind @ ("win" | "loss")) => format!("{} {}", kind, tokens.next().unwrap_or("")).parse(),
+        value => parse_value(name, value),
+    }
+}
+
+// For limits where 0 would mean a search that can never finish its first iteration
+fn parse_positive(name: &str, value: Option<&str>) -> Result<usize, String> {
+    match parse_value(name, value)? {
+        0 => Err(format!("{} needs to be at least 1!", name)),
+        value => Ok(value),
+    }
+}
+
+fn parse_millis(name: &str, value: Option<&str>) -> Result<Duration, String> {
+    parse_value(name, value).map(Duration::from_millis)
+}
+
+impl FromStr for Command {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut tokens = s.split_whitespace();
+        let command = tokens.next().ok_or(String::from("Empty command!"))?;
+
+        match command {
+            "sei" => Ok(Command::Sei),
+            "isready" => Ok(Command::IsReady),
+            "newgame" => Ok(Command::NewGame),
+            "stop" => Ok(Command::Stop),
+            "quit" => Ok(Command::Quit),
+            "setoption" => {
+                // setoption name NAME value VALUE, where both can contain spaces
+                let rest: Vec<&str> = tokens.collect();
+                let rest = rest.join(" ");
+                let rest = rest.strip_prefix("name ").ok_or(format!("Expected setoption name NAME value VALUE, got \"{}\"!", s))?;
+                let (name, value) = match rest.split_once(" value ") {
+                    Some((name, value)) => (name, value),
+                    None => (rest, ""),
+                };
+                Ok(Command::SetOption {
+                    name: name.trim().to_string(),
+                    value: value.trim().to_string(),
+                })
+            }
+            "position" => {
+                let rest: Vec<&str> = tokens.collect();
+                let (board, plies) = match rest.iter().position(|&x| x == "moves") {
+                    Some(index) => (&rest[..index], &rest[index + 1..]),
+                    None => (&rest[..], &[][..]),
+                };
+                Ok(Command::Position {
+                    board: board.join(" ").parse()?,
+                    plies: plies.iter().map(|x| x.parse()).collect::<Result<Vec<Ply>, String>>()?,
+                })
+            }
+            "go" => {
+                let mut limits = SearchLimits::default();
+                while let Some(name) = tokens.next() {
+                    match name {
+                        "p1time" => limits.time[P1] = Some(parse_millis(name, tokens.next())?),
+                        "p2time" => limits.time[P2] = Some(parse_millis(name, tokens.next())?),
+                        "p1inc" => limits.increment[P1] = parse_millis(name, tokens.next())?,
+                        "p2inc" => limits.increment[P2] = parse_millis(name, tokens.next())?,
+                        "movestogo" => limits.moves_to_go = Some(parse_value(name, tokens.next())?),
+                        "movetime" => limits.movetime = Some(parse_millis(name, tokens.next())?),
+                        "depth" => limits.depth = Some(parse_positive(name, tokens.next())?),
+                        "nodes" => limits.nodes = Some(parse_value(name, tokens.next())?),
+                        "mate" => limits.mate = Some(parse_positive(name, tokens.next())?),
+                        "infinite" => limits.infinite = true,
+                        "multipv" => limits.multi_pv = Some(parse_value(name, tokens.next())?),
+                        _ => return Err(format!("Unknown go limit {}!", name)),
+                    }
+                }
+                Ok(Command::Go(limits))
+            }
+            _ => Err(format!("Unknown command {}!", command)),
+        }
+    }
+}
+impl Display for Command {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Command::Sei => write!(f, "sei"),
+            Command::IsReady => write!(f, "isready"),
+            Command::NewGame => write!(f, "newgame"),
+            Command::SetOption { name, value } => write!(f, "setoption name {} value {}", name, value),
+            Command::Position { board, plies } => {
+                write!(f, "position {}", board.to_notation())?;
+                if !plies.is_empty() {
+                    let plies: Vec<String> = plies.iter().map(|x| x.to_string()).collect();
+                    write!(f, " moves {}", plies.join(" "))?;
+                }
+                Ok(())
+            }
+            Command::Go(limits) => {
+                write!(f, "go")?;
+                if let Some(time) = limits.time[P1] {
+                    write!(f, " p1time {}", time.as_millis())?;
+                }
+                if let Some(time) = limits.time[P2] {
+                    write!(f, " p2time {}", time.as_millis())?;
+                }
+                if !limits.increment[P1].is_zero() {
+                    write!(f, " p1inc {}", limits.increment[P1].as_millis())?;
+                }
+                if !limits.increment[P2].is_zero() {
+                    write!(f, " p2inc {}", limits.increment[P2].as_millis())?;
+                }
+                if let Some(moves_to_go) = limits.moves_to_go {
+                    write!(f, " movestogo {}", moves_to_go)?;
+                }
+                if let Some(movetime) = limits.movetime {
+                    write!(f, " movetime {}", movetime.as_millis())?;
+                }
+                if let Some(depth) = limits.depth {
+                    write!(f, " depth {}", depth)?;
+                }
+                if let Some(nodes) = limits.nodes {
+                    write!(f, " nodes {}", nodes)?;
+                }
+                if let Some(mate) = limits.mate {
+                    write!(f, " mate {}", mate)?;
+                }
+                if limits.infinite {
+                    write!(f, " infinite")?;
+                }
+                if let Some(multi_pv) = limits.multi_pv {
+                    write!(f, " multipv {}", multi_pv)?;
+                }
+                Ok(())
+            }
+            Command::Stop => write!(f, "stop"),
+            Command::Quit => write!(f, "quit"),
+        }
+    }
+}
+
+impl FromStr for Response {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut tokens = s.split_whitespace();
+        let response = tokens.next().ok_or(String::from("Empty response!"))?;
+
+        match response {
+            "id" => match tokens.next() {
+                Some("name") => Ok(Response::IdName(tokens.collect::<Vec<&str>>().join(" "))),
+                Some("evalrange") => {
+                    let min = parse_value("evalrange", tokens.next())?;
+                    let max = parse_value("evalrange", tokens.next())?;
+                    Ok(Response::IdEvalRange(min, max))
+                }
+                _ => Err(format!("Unknown id line \"{}\"!", s)),
+            },
+            "option" => match tokens.next() {
+                Some("name") => Ok(Response::Option(tokens.collect::<Vec<&str>>().join(" "))),
+                _ => Err(format!("Expected option name NAME, got \"{}\"!", s)),
+            },
+            "seiok" => Ok(Response::SeiOk),
+            "error" => Ok(Response::Error(tokens.collect::<Vec<&str>>().join(" "))),
+            "readyok" => Ok(Response::ReadyOk),
+            "bestmove" => match tokens.next() {
+                Some("none") => Ok(Response::BestMove(None)),
+                Some(ply) => Ok(Response::BestMove(Some(ply.parse()?))),
+                None => Err(String::from("Missing best move!")),
+            },
+            "info" => {
+                let mut info = Info::default();
+                while let Some(name) = tokens.next() {
+                    match name {
+                        "string" => return Ok(Response::InfoString(tokens.collect::<Vec<&str>>().join(" "))),
+                        "multipv" => info.multipv = Some(parse_value(name, tokens.next())?),
+                        "depth" => info.depth = Some(parse_value(name, tokens.next())?),
+                        "seldepth" => info.seldepth = Some(parse_value(name, tokens.next())?),
+                        "score" => info.score = Some(parse_score(name, &mut tokens)?),
+                        "nodes" => info.nodes = Some(parse_value(name, tokens.next())?),
+                        "nps" => info.nps = Some(parse_value(name, tokens.next())?),
+                        "hashfull" => info.hashfull = Some(parse_value(name, tokens.next())?),
+                        "time" => info.time = Some(parse_millis(name, tokens.next())?),
+                        // The principal variation always comes last
+                        "pv" => {
+                            info.pv = Some(tokens.map(|x| x.parse()).collect::<Result<Vec<Move>, String>>()?);
+                            break;
+                        }
+                        _ => return Err(format!("Unknown info field {}!", name)),
+                    }
+                }
+                Ok(Response::Info(info))
+            }
+            _ => Err(format!("Unknown response {}!", response)),
+        }
+    }
+}
+impl Display for Response {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Response::IdName(name) => write!(f, "id name {}", name),
+            Response::IdEvalRange(min, max) => write!(f, "id evalrange {} {}", min, max),
+            Response::Option(name) => write!(f, "option name {}", name),
+            Response::SeiOk => write!(f, "seiok"),
+            Response::ReadyOk => write!(f, "readyok"),
+            Response::Info(info) => {
+                write!(f, "info")?;
+                if let Some(multipv) = info.multipv {
+                    write!(f, " multipv {}", multipv)?;
+                }
+                if let Some(depth) = info.depth {
+                    write!(f, " depth {}", depth)?;
+                }
+                if let Some(seldepth) = info.seldepth {
+                    write!(f, " seldepth {}", seldepth)?;
+                }
+                if let Some(score) = info.score {
+                    write!(f, " score {}", score)?;
+                }
+                if let Some(nodes) = info.nodes {
+                    write!(f, " nodes {}", nodes)?;
+                }
+                if let Some(nps) = info.nps {
+                    write!(f, " nps {}", nps)?;
+                }
+                if let Some(hashfull) = info.hashfull {
+                    write!(f, " hashfull {}", hashfull)?;
+                }
+                if let Some(time) = info.time {
+                    write!(f, " time {}", time.as_millis())?;
+                }
+                if let Some(pv) = &info.pv {
+                    let pv: Vec<String> = pv.iter().map(|x| x.to_string()).collect();
+                    write!(f, " pv {}", pv.join(" "))?;
+                }
+                Ok(())
+            }
+            Response::InfoString(text) => write!(f, "info string {}", text),
+            Response::BestMove(Some(ply)) => write!(f, "bestmove {}", ply),
+            Response::BestMove(None) => write!(f, "bestmove none"),
+            Response::Error(message) => write!(f, "error {}", message),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn commands_round_trip() {
+        let commands = [
+            "sei",
+            "isready",
+            "newgame",
+            "setoption name Hash size value 64",
+            "position 00000/00000/00000/00000/00000 - - P1",
+            "position 00000/00000/00000/00000/00000 - - P1 moves C3,B3 C2,D4 B3-B4^B5",
+            "go p1time 60000 p2time 55000",
+            "go p1time 60000 p2time 55000 p1inc 1000 p2inc 1000",
+            "go p1time 30000 p2time 30000 movestogo 12",
+            "go movetime 1000 depth 8",
+            "go nodes 100000 mate 3",
+            "go infinite",
+            "go depth 6 multipv 3",
+            "stop",
+            "quit",
+        ];
+        for text in commands {
+            let command: Command = text.parse().unwrap();
+            assert_eq!(command.to_string(), text);
+        }
+    }
+
+    #[test]
+    fn command_fields() {
+        let command: Command = "setoption name Hash size value 64".parse().unwrap();
+        assert_eq!(command, Command::SetOption { name: String::from("Hash size"), value: String::from("64") });
+
+        let command: Command = "go p2time 1500 depth 4".parse().unwrap();
+        assert_eq!(command, Command::Go(SearchLimits {
+            time: [None, Some(Duration::from_millis(1500))],
+            depth: Some(4),
+            ..Default::default()
+        }));
+
+        let command: Command = "position 00000/00000/00000/00000/00000 B3,C2 C4,D3 P1 moves B3-A3^A4".parse().unwrap();
+        assert_eq!(command, Command::Position {
+            board: Board::new([B3, C2], [C4, D3]),
+            plies: vec![Ply::Move(Move { from: B3, to: A3, at: Some(A4) })],
+        });
+    }
+
+    #[test]
+    fn command_errors() {
+        assert!("".parse::<Command>().is_err());
+        assert!("dance".parse::<Command>().is_err());
+        assert!("go depth".parse::<Command>().is_err());
+        assert!("go depth 0".parse::<Command>().is_err());
+        assert!("go mate 0".parse::<Command>().is_err());
+        assert!("go p1time soon".parse::<Command>().is_err());
+        assert!("go nodes".parse::<Command>().is_err());
+        assert!("go ponder".parse::<Command>().is_err());
+        assert!("position 00000/00000/00000/00000 - - P1".parse::<Command>().is_err());
+        assert!("position 00000/00000/00000/00000/00000 - - P1 moves C3".parse::<Command>().is_err());
+        assert!("setoption value 3".parse::<Command>().is_err());
+    }
+
+    #[test]
+    fn responses_round_trip() {
+        let responses = [
+            "id name spectre",
+            "id evalrange -46 46",
+            "option name Hash",
+            "seiok",
+            "readyok",
+            "info depth 6 score -3 time 812 pv B3-B4^B5 D4-D3^D2",
+            "info depth 2",
+            "info multipv 2 depth 5 score -4 pv C3-C4^C5",
+            "info depth 3 score win 3 pv B3-B4^B5 C4-C3^C4 B4-A4",
+            "info depth 4 score loss 2",
+            "info depth 4 seldepth 4 score 0 nodes 1500 nps 30000 hashfull 12 time 50 pv C3-C4^C5",
+            "info string depth limits are ignored",
+            "bestmove B3-B4^B5",
+            "bestmove C3,B3",
+            "bestmove none",
+            "error index out of bounds",
+        ];
+        for text in responses {
+            let response: Response = text.parse().unwrap();
+            assert_eq!(response.to_string(), text);
+        }
+    }
+
+    #[test]
+    fn response_errors() {
+        assert!("bestmove".parse::<Response>().is_err());
+        assert!("bestmove C3".parse::<Response>().is_err());
+        assert!("info colour 4".parse::<Response>().is_err());
+        assert!("id colour".parse::<Response>().is_err());
+        assert!("option Hash".parse::<Response>().is_err());
+        assert!("info score win".parse::<Response>().is_err());
+        assert!("info score draw 3".parse::<Response>().is_err());
+    }
+}
@@ -1,45 +1,32 @@
-mod board_rep;
-mod eval;
-mod time_management;
+mod placement;
+mod tt;
 pub mod search;
-use crate::{Move, Board, Engine, EngineInfo, Request, SearchResult};
 
-use self::search::{SearchRequest, get_best_move};
-use self::time_management::get_time;
+use crate::*;
+
+use std::sync::Mutex;
+use std::time::Instant;
+
+use crate::core::time_management::get_thinking_time;
+use self::placement::{PlacementRequest, get_best_placement};
+use crate::core::search::{SearchRequest, MAX_DEPTH};
+use self::search::get_best_move;
+use self::tt::{TranspositionTable, DEFAULT_SIZE_MB};
 pub use self::search::spectre_v1_benchmark;
 pub use self::search::spectre_v2_benchmark;
 
-fn convert_move(board: board_rep::Board, internal_move: board_rep::Move) -> Move {
-    let build = if board.blocks[internal_move.to] == 3 {
-        None
-    } else {
-        Some(internal_move.build)
-    };
-    Move {
-        from: internal_move.from,
-        to: internal_move.to,
-        build,
-    }
+// The transposition table is kept from one search to the next. Engine only lends &self while
+// searching, hence the Mutex.
+pub struct Spectre {
+    tt: Mutex<TranspositionTable>,
 }
-
-fn convert_board(board: Board) -> board_rep::Board {
-    board_rep::Board {
-        blocks: board.blocks,
-        workers: board.workers,
-        turn: board.turn,
+pub fn new() -> Spectre {
+    Spectre {
+        tt: Mutex::new(TranspositionTable::new(DEFAULT_SIZE_MB)),
     }
 }
 
-pub struct Spectre;
-
 impl Engine for Spectre {
-    fn new() -> Self
-    where
-        Self: Sized,
-    {
-        Spectre {}
-    }
-
     fn get_info(&self) -> EngineInfo {
         EngineInfo {
             name: String::from("spectre"),
@@ -47,16 +34,44 @@ impl Engine for Spectre {
         }
     }
 
-    fn get_move(&self, request: Request) -> SearchResult {
-        let thinking_time = get_time(request.time_left);
-        let request = SearchRequest {
-            position: convert_board(request.board),
-            // We use a fixed depth to avoid growing to unnecessary depths when a game-ending move is found
-            max_depth: 20,
-            time_left: Some(thinking_time),
-            debug: false,
+    fn get_search_result(&self, request: Request) -> SearchResult {
+        get_best_move(SearchRequest::from_request(request, false), &mut self.tt.lock().unwrap())
+    }
+
+    fn get_placement_result(&self, request: Request) -> PlacementResult {
+        let start = Instant::now();
+        let placed = Square::squares()
+            .into_iter()
+            .filter(|&square| request.workers[square].is_some())
+            .map(|square| square.into())
+            .collect();
+        let request = PlacementRequest {
+            blocks: request.blocks.map(|x| Into::<usize>::into(x) as u8),
+            placed,
+            max_depth: request.limits.get_max_depth().unwrap_or(MAX_DEPTH),
+            time_left: get_thinking_time(&request.limits, *request.turn),
+            control: request.control.clone(),
         };
-        get_best_move(request)
+        let (squares, eval) = get_best_placement(request, &mut self.tt.lock().unwrap());
+
+        PlacementResult {
+            squares: squares.map(|x| x.try_into().unwrap()),
+            eval: Some(Score::from_value(eval)),
+            time_spent: Some(start.elapsed()),
+        }
+    }
+
+    fn get_options(&self) -> Vec<String> {
+        vec![String::from("Hash")]
+    }
+    // "Hash" sets the size of the transposition table in MB, emptying it
+    fn set_option(&mut self, name: &str, value: &str) -> Result<(), String> {
+        if !name.eq_ignore_ascii_case("hash") {
+            return Err(format!("spectre has no option {}!", name));
+        }
+        let size_mb: usize = value.parse().map_err(|_| format!("{} is not a valid size in MB!", value))?;
+        self.tt = Mutex::new(TranspositionTable::try_new(size_mb)?);
+        Ok(())
     }
 }
 
@@ -64,23 +79,50 @@ impl Engine for Spectre {
 mod tests {
     use std::time::Duration;
 
-    use crate::helpers::squares::*;
-    use crate::helpers::turn::*;
 
     use super::*;
     #[test]
     fn t1() {
-        let board = crate::models::Board {
-            blocks: [0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0],
-            workers: [C2, C3, C4, C5],
-            turn: U,
-        };
+        let board = Board::new_custom(Default::default(), [C2, C3], [C4, C5], P2);
         let total_time = Duration::from_secs(60);
-        let flop = Spectre {};
-        let mv = flop.get_move(Request {
-            board,
-            time_left: total_time,
-        });
+        let spectre = new();
+        let mv = spectre.get_move(board.get_request(SearchLimits::clock(board.get_turn(), total_time)));
         dbg!(&mv);
     }
+
+    #[test]
+    fn stopped_search_keeps_table_sound() {
+        let board: Board = "10110/10110/01301/00000/00200 B1,B3 B5,C4 P2".parse().unwrap();
+        let fresh = new().get_search_result(board.get_request(SearchLimits::depth(4)));
+
+        let spectre = new();
+        spectre.get_search_result(board.get_request(SearchLimits { nodes: Some(3000), ..Default::default() }));
+        let result = spectre.get_search_result(board.get_request(SearchLimits::depth(4)));
+        assert_eq!((result.mv, result.eval), (fresh.mv, fresh.eval));
+    }
+
+    #[test]
+    fn warm_table_keeps_result() {
+        let boards = [
+            "00000/00000/00000/00100/00000 B2,E4 C2,C4 P2",
+            "00200/13300/10021/03133/04101 B4,C2 E1,E3 P2",
+        ];
+        for board in boards {
+            let board: Board = board.parse().unwrap();
+            let spectre = new();
+            let cold = spectre.get_search_result(board.get_request(SearchLimits::depth(5)));
+            let warm = spectre.get_search_result(board.get_request(SearchLimits::depth(5)));
+            assert_eq!((warm.mv, warm.eval), (cold.mv, cold.eval), "{}", board.to_notation());
+        }
+    }
+
+    #[test]
+    fn hash_option() {
+        let mut spectre = new();
+        assert!(spectre.set_option("Hash", "64").is_ok());
+        for value in ["0", "none", "4097", "18446744073709551615"] {
+            assert!(spectre.set_option("Hash", value).is_err(), "{} MB was accepted", value);
+        }
+        assert!(spectre.set_option("Contempt", "16").is_err());
+    }
 }
@@ -0,0 +1,158 @@
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use itertools::Itertools;
+
+use crate::helpers::Deadline;
+use crate::helpers::turn::*;
+use crate::models::SearchControl;
+use crate::core::board_rep::*;
+use crate::core::eval::*;
+use crate::spectre::search::get_position_value;
+use crate::spectre::tt::TranspositionTable;
+
+pub struct PlacementRequest {
+    pub blocks: [u8; 25],
+    // The first player's workers, empty when placing first
+    pub placed: Vec<usize>,
+    pub max_depth: usize,
+    pub time_left: Option<Duration>,
+    pub control: Arc<SearchControl>,
+}
+
+// Workers can't be placed on domes, nor on the third level where they would have already won
+fn get_candidates(blocks: &[u8; 25], taken: &[usize]) -> Vec<[usize; 2]> {
+    (0..25)
+        .filter(|square| !taken.contains(square) && blocks[*square] < 3)
+        .tuple_combinations()
+        .map(|(a, b)| [a, b])
+        .collect()
+}
+
+// The first player can't search without the opponent's workers, so it assumes they answer
+// with the placement the eval likes best for them
+fn get_first_placement(blocks: [u8; 25]) -> ([usize; 2], isize) {
+    get_candidates(&blocks, &[])
+        .into_iter()
+        .map(|mine| {
+            let worst = get_candidates(&blocks, &mine)
+                .into_iter()
+                .map(|theirs| eval(&Board::new(blocks, [mine[0], mine[1], theirs[0], theirs[1]], W)))
+                .min()
+                .unwrap();
+            (mine, worst)
+        })
+        .max_by_key(|&(_, worst)| worst)
+        .unwrap()
+}
+
+// The second player searches every candidate with iterative deepening and keeps the one
+// whose value is the worst for the first player, who moves next
+fn get_second_placement(blocks: [u8; 25], first: [usize; 2], max_depth: usize, deadline: &Deadline, tt: &mut TranspositionTable) -> ([usize; 2], isize) {
+    let positions: Vec<([usize; 2], Board)> = get_candidates(&blocks, &first)
+        .into_iter()
+        .map(|mine| (mine, Board::new(blocks, [first[0], first[1], mine[0], mine[1]], W)))
+        .collect();
+
+    let mut best = positions
+        .iter()
+        .map(|(mine, board)| (*mine, -eval(board)))
+        .max_by_key(|&(_, value)| value)
+        .unwrap();
+
+    for depth in 1..=max_depth {
+        let mut depth_best: Option<([usize; 2], isize)> = None;
+        for (mine, board) in &positions {
+            let value = match get_position_value(*board, depth, deadline, tt) {
+                Some(value) => -value,
+                None => return best,
+            };
+            if depth_best.is_none_or(|(_, best_value)| value > best_value) {
+                depth_best = Some((*mine, value));
+            }
+        }
+        best = depth_best.unwrap();
+
+        if deadline.out_of_time() {
+            break;
+        }
+    }
+
+    best
+}
+
+// Returns the chosen squares and their value for the player placing
+pub fn get_best_placement(request: PlacementRequest, tt: &mut TranspositionTable) -> ([usize; 2], isize) {
+    let deadline = Deadline {
+        stop_at: request.time_left.map(|x| Instant::now() + x),
+        max_nodes: None,
+        control: request.control.clone(),
+    };
+
+    tt.new_search();
+    match request.placed[..] {
+        [] => get_first_placement(request.blocks),
+        [a, b] => get_second_placement(request.blocks, [a, b], request.max_depth, &deadline, tt),
+        _ => panic!("Placement needs zero or two workers already on the board!"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::helpers::squares::*;
+
+    const INNER_SQUARES: [usize; 9] = [B2, B3, B4, C2, C3, C4, D2, D3, D4];
+
+    #[test]
+    fn candidates() {
+        assert_eq!(get_candidates(&[0; 25], &[]).len(), 300);
+        assert_eq!(get_candidates(&[0; 25], &[A1, A2]).len(), 253);
+
+        let mut blocks = [0; 25];
+        blocks[C3] = 4;
+        blocks[C4] = 3;
+        assert_eq!(get_candidates(&blocks, &[]).len(), 253);
+    }
+
+    #[test]
+    fn first_placement_is_central() {
+        let (squares, _) = get_first_placement([0; 25]);
+        assert!(INNER_SQUARES.contains(&squares[0]));
+        assert!(INNER_SQUARES.contains(&squares[1]));
+    }
+
+    #[test]
+    fn second_placement_avoids_first() {
+        let request = PlacementRequest {
+            blocks: [0; 25],
+            placed: vec![C3, B3],
+            max_depth: 1,
+            time_left: None,
+            control: Arc::default(),
+        };
+        let (squares, _) = get_best_placement(request, &mut TranspositionTable::new(1));
+        assert!(!squares.contains(&C3) && !squares.contains(&B3));
+        assert!(INNER_SQUARES.contains(&squares[0]));
+    }
+
+    #[test]
+    fn second_placement_smothers() {
+        // Each of P1's workers has a single way out left
+        let mut blocks = [0; 25];
+        blocks[A2] = 4;
+        blocks[B1] = 4;
+        blocks[D4] = 4;
+        blocks[D5] = 4;
+        let request = PlacementRequest {
+            blocks,
+            placed: vec![A1, E5],
+            max_depth: 2,
+            time_left: None,
+            control: Arc::default(),
+        };
+        let (squares, value) = get_best_placement(request, &mut TranspositionTable::new(1));
+        assert_eq!(squares, [B2, E4]);
+        assert!(value > 46);
+    }
+}
@@ -1,103 +1,61 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::ops::Add;
-use std::time::Duration;
 use std::time::Instant;
 
-use crate::BenchmarkRequest;
-use crate::helpers::print_with_timestamp;
-use crate::helpers::turn::*;
-use crate::spectre::board_rep::*;
-use crate::spectre::eval::*;
-use crate::models::SearchResult;
+use crate::helpers::{get_loss_value, print_with_timestamp, value_from_tt, value_to_tt, Deadline, SearchStats};
+use crate::core::board_rep::*;
+use crate::core::eval::*;
+use crate::spectre::tt::{TTEntry, TranspositionTable, DEFAULT_SIZE_MB};
+use crate::models::{Request, RootMoveResult, Score, SearchResult};
 
-use super::convert_move;
+use crate::core::{convert_move, convert_pv};
+use crate::core::search::{SearchRequest, CHECK_CLOCK_EVERY};
 
-const BIG_ENOUGH_VALUE:isize = 10000;
-const CHECK_CLOCK_EVERY:usize = 1000;
-
-pub struct SearchRequest{
-    pub position:Board,
-    pub max_depth:usize,
-    pub time_left:Option<Duration>,
-    pub debug: bool,
-}
-#[derive(Clone, Debug)]
-struct TTEntry{
-    pub depth: u8,
-    pub flag: char,
-    pub value: isize,
-    pub mv: Move,
+pub fn spectre_v1_benchmark(request:Request)-> SearchResult{
+    get_move(SearchRequest::from_request(request, true))
 }
 
-fn prepare_to_benchmark(func: fn(SearchRequest) -> SearchResult) -> impl Fn(BenchmarkRequest) -> SearchResult {
-    move |benchmark_request| {
-        let internal_board = Board {
-            blocks: benchmark_request.position.blocks,
-            workers: benchmark_request.position.workers,
-            turn: benchmark_request.position.turn,
-        };
-
-        let request = SearchRequest {
-            position: internal_board,
-            max_depth: benchmark_request.max_depth,
-            time_left: None,
-            debug: true,
-        };
-        func(request)
-    }
-}
-pub fn spectre_v1_benchmark(br:BenchmarkRequest)-> SearchResult{
-    prepare_to_benchmark(get_move)(br)
+pub fn spectre_v2_benchmark(request:Request)-> SearchResult{
+    get_move_full_tt(SearchRequest::from_request(request, true), &mut TranspositionTable::new(DEFAULT_SIZE_MB))
 }
 
-pub fn spectre_v2_benchmark(br:BenchmarkRequest)-> SearchResult{
-    prepare_to_benchmark(get_move_full_tt)(br)
-}
-    
-fn get_color(node:&Board) -> isize{
-    match node.turn {
-        W => 1,
-        U => -1,
-        _ => unreachable!(),
-    }
-} 
-
+#[allow(clippy::too_many_arguments)]
 fn alphabeta_tt (
     node: &mut Board,
     depth: usize,
     ply: usize,
     mut alpha: isize,
     beta: isize,
-    nodes_searched: &mut usize,
-    stop_at: Instant,
-    tt: &mut HashMap<Board, Move>,
+    stats: &mut SearchStats,
+    deadline: &Deadline,
+    tt: &mut HashMap<u64, Move>,
     running:&mut bool,
 ) -> isize {
-    *nodes_searched += 1;
-    if (! *running) || ((*nodes_searched % CHECK_CLOCK_EVERY == 0) && Instant::now() > stop_at) {
+    stats.visit(ply);
+    if (! *running) || deadline.out_of_nodes(stats.nodes) || (stats.nodes.is_multiple_of(CHECK_CLOCK_EVERY) && deadline.should_stop(stats.nodes)) {
         *running = false;
         return 0;
     }
-    let color = get_color(node);
+    let color = node.get_color();
 
     if node.game_is_over() {
-        return -BIG_ENOUGH_VALUE - (depth - ply) as isize;
+        return get_loss_value(ply);
     }
 
     if ply == depth {
         return color * eval(node);
     }
 
-    let mut value = -BIG_ENOUGH_VALUE * 100;
+    let mut value = -Score::WIN_VALUE * 100;
 
-    if let Some(mv) = tt.get(node).cloned() {
+    if let Some(mv) = tt.get(&node.get_hash()).cloned() {
         node.make_move(mv);
-        let new_value = -alphabeta_tt(node, depth, ply + 1, -beta, -alpha, nodes_searched, stop_at, tt, running);
+        let new_value = -alphabeta_tt(node, depth, ply + 1, -beta, -alpha, stats, deadline, tt, running);
         node.undo_move(mv);
 
         if new_value > value {
             value = new_value;
-            tt.insert(*node, mv);
+            tt.insert(node.get_hash(), mv);
         }
 
         if value > alpha {
@@ -109,21 +67,22 @@ fn alphabeta_tt (
         }
     }
 
-    let moves = node.generate_moves();
+    let mut moves = MoveList::new();
+    node.generate_moves(&mut moves);
 
     if moves.is_empty() {
-        return -BIG_ENOUGH_VALUE - (depth - ply) as isize;
+        return get_loss_value(ply);
     }
 
-    for mv in moves {
-        if let Some(m) = tt.get_mut(node) {
+    for &mv in moves.iter() {
+        if let Some(m) = tt.get(&node.get_hash()) {
             if *m == mv {
                 continue;
             }
         }
 
         node.make_move(mv);
-        let new_value = -alphabeta_tt(node, depth, ply + 1, -beta, -alpha, nodes_searched, stop_at, tt, running);
+        let new_value = -alphabeta_tt(node, depth, ply + 1, -beta, -alpha, stats, deadline, tt, running);
         node.undo_move(mv);
 
         if new_value > value {
@@ -132,7 +91,7 @@ fn alphabeta_tt (
 
         if value > alpha {
             alpha = value;
-            tt.insert(*node, mv);
+            tt.insert(node.get_hash(), mv);
         }
 
         if alpha >= beta {
@@ -143,42 +102,72 @@ fn alphabeta_tt (
     value
 }
 
-fn get_move(request: SearchRequest) -> SearchResult{ 
-    let thinking_time = request.time_left.unwrap_or(Duration::from_secs(10000));
-
+// Follows the best moves stored in the TT from the root. Entries may have been overwritten by
+// other lines since, so the walk stops at a move that isn't legal, once the game is over, when a
+// position comes back or after depth moves.
+fn get_tt_pv(mut board: Board, depth: usize, best_move: impl Fn(&Board) -> Option<Move>) -> Vec<Move> {
+    let mut pv = vec![];
+    let mut seen = HashSet::new();
+    let mut moves = MoveList::new();
+    while pv.len() < depth && !board.game_is_over() && seen.insert(board.get_hash()) {
+        let Some(mv) = best_move(&board) else {
+            break;
+        };
+        board.generate_moves(&mut moves);
+        if !moves.contains(&mv) {
+            break;
+        }
+        board.make_move(mv);
+        pv.push(mv);
+    }
+    pv
+}
 
+fn get_move(request: SearchRequest) -> SearchResult{ 
     let current_time = Instant::now();
-    let limit_time = current_time.add(thinking_time);
+    let deadline = Deadline {
+        stop_at: request.time_left.map(|x| current_time.add(x)),
+        max_nodes: request.max_nodes,
+        control: request.control.clone(),
+    };
+    // The first iteration always finishes so there is a move to play
+    let no_deadline = Deadline::default();
 
     let mut running = true;
-    let mut board = Board {
-        blocks: request.position.blocks,
-        workers: request.position.workers,
-        turn: request.position.turn,
-    };
-    let mut tt: HashMap<Board, Move> = HashMap::new();
+    let mut board = Board::new(request.position.blocks, request.position.workers, request.position.turn);
+    let mut tt: HashMap<u64, Move> = HashMap::new();
     let mut depth = 0;
-    let mut best_score = -BIG_ENOUGH_VALUE;
-    let mut nodes_searched = 0;
+    let mut depth_searched = 0;
+    let mut best_score = -Score::WIN_VALUE;
+    let mut pv = vec![];
+    let mut stats = SearchStats::default();
     while running {
         depth += 1;
         if request.debug{
             print_with_timestamp(&format!("Starting depth: {}", depth));
         }
-        let result = alphabeta_tt(&mut board, depth, 0, -BIG_ENOUGH_VALUE, BIG_ENOUGH_VALUE, &mut nodes_searched, limit_time, &mut tt, &mut running);
+        let result = alphabeta_tt(&mut board, depth, 0, -Score::WIN_VALUE, Score::WIN_VALUE, &mut stats, if depth == 1 { &no_deadline } else { &deadline }, &mut tt, &mut running);
         
-        if result > best_score{
-            best_score = result;
+        // Values of unfinished iterations are meaningless
+        if running {
+            if result > best_score {
+                best_score = result;
+            }
+            depth_searched = depth;
+            pv = get_tt_pv(board, depth, |x| tt.get(&x.get_hash()).copied());
+            if let Some(sink) = &request.sink {
+                sink.send(&stats.get_info(depth, result, current_time.elapsed(), convert_pv(&board, &pv), None));
+            }
         }
 
-        if depth == request.max_depth {
+        if depth >= request.max_depth {
             running = false;
         } 
     }
     let end_time = Instant::now();
     let time_spent_thinking = end_time - current_time;
 
-    let best= tt.get(&board);
+    let best= tt.get(&board.get_hash());
 
     let best_move = best;
     let best_score = best_score;
@@ -188,105 +177,131 @@ fn get_move(request: SearchRequest) -> SearchResult{
     }
 
     SearchResult {
-        mv: convert_move(board, *(best_move.unwrap_or(&Move{from:0, to:0, build:0}))),
-        eval: Some(best_score),
-        pv: None,
+        mv: convert_move(&board, *(best_move.unwrap_or(&Move{from:0, to:0, build:0}))),
+        eval: Some(Score::from_value(best_score)),
+        pv: Some(convert_pv(&board, &pv)),
         time_spent: Some(time_spent_thinking),
-        depth_searched: Some(depth),
+        depth_searched: Some(depth_searched),
+        // This version doesn't do multi-PV, so only its best move is ranked
+        root_moves: pv
+            .first()
+            .map(|&mv| RootMoveResult {
+                mv: convert_move(&board, mv),
+                eval: Score::from_value(best_score),
+                pv: convert_pv(&board, &pv),
+            })
+            .into_iter()
+            .collect(),
     }
     
 }
 
 
+#[allow(clippy::too_many_arguments)]
 fn alphabeta_full_tt (
     node: &mut Board,
     depth: usize,
     ply: usize,
     mut alpha: isize,
     mut beta: isize,
-    nodes_searched: &mut usize,
-    stop_at: Instant,
-    tt: &mut HashMap<Board, TTEntry>,
+    stats: &mut SearchStats,
+    deadline: &Deadline,
+    tt: &mut TranspositionTable,
     running:&mut bool,
 ) -> isize {
-    *nodes_searched += 1;
-    if (! *running) || ((*nodes_searched % CHECK_CLOCK_EVERY == 0) && Instant::now() > stop_at) {
+    stats.visit(ply);
+    if (! *running) || deadline.out_of_nodes(stats.nodes) || (stats.nodes.is_multiple_of(CHECK_CLOCK_EVERY) && deadline.should_stop(stats.nodes)) {
         *running = false;
         return 0;
     }
 
-    let color = get_color(node);
+    let color = node.get_color();
 
     if node.game_is_over() {
-        return -BIG_ENOUGH_VALUE - (depth - ply) as isize;
+        return get_loss_value(ply);
     }
 
     if ply == depth {
         return color * eval(node);
     }
 
-    let alpha_orig = alpha;
-    let entry_opt = tt.get(node).cloned();
+    let hash = node.get_hash();
+    let entry_opt = tt.get(hash);
 
-    let mut value = -BIG_ENOUGH_VALUE * 100;
+    let mut value = -Score::WIN_VALUE * 100;
     let mut best_move = Move{from: 0, build: 0, to:0};
 
-    match entry_opt{
-        Some(entry) => {
-            if entry.depth == (depth - ply) as u8{
-                if entry.flag == 'E'{
-                    return entry.value as isize;
-                }
-                else if entry.flag == 'U' && (entry.value as isize) > alpha{
-                    alpha = entry.value as isize;
-                }
-                else if entry.flag == 'L' && (entry.value as isize) < beta{
-                    beta = entry.value as isize;
-                }
+    if let Some(entry) = &entry_opt {
+        let entry_value = value_from_tt(entry.value, ply);
+        if entry.depth == (depth - ply) as u8{
+            if entry.flag == 'E'{
+                return entry_value;
             }
-
-            node.make_move(entry.mv);
-            let new_value = -alphabeta_full_tt(node, depth, ply + 1, -beta, -alpha, nodes_searched, stop_at, tt, running);
-            node.undo_move(entry.mv);
-
-            if new_value > value {
-                value = new_value;
-                best_move = entry.mv;
+            else if entry.flag == 'L' && entry_value > alpha{
+                alpha = entry_value;
             }
-
-            if value > alpha {
-                alpha = value;
+            else if entry.flag == 'U' && entry_value < beta{
+                beta = entry_value;
             }
-
             if alpha >= beta {
-                let flag =
-                    if value <= alpha_orig {'U'}
-                    else if value >= beta {'L'}
-                    else{'E'};
-
-                let new_entry =
-                TTEntry{
-                    depth: (depth - ply) as u8,
-                    flag: flag,
-                    value: value,
-                    mv: entry.mv,
-                };
-                tt.insert(*node, new_entry);
-                return value;
+                return entry_value;
             }
         }
-        None => (),
     }
+    // The flag of what is stored is decided against the window left after the probe
+    let alpha_orig = alpha;
 
-    let moves = node.generate_moves();
+    let mut moves = MoveList::new();
+    node.generate_moves(&mut moves);
 
     if moves.is_empty() {
-        return -BIG_ENOUGH_VALUE - (depth - ply) as isize;
+        return get_loss_value(ply);
+    }
+
+    // A colliding key can hand over a move from another position
+    if let Some(entry) = entry_opt.filter(|entry| moves.contains(&entry.mv)) {
+        node.make_move(entry.mv);
+        let new_value = -alphabeta_full_tt(node, depth, ply + 1, -beta, -alpha, stats, deadline, tt, running);
+        node.undo_move(entry.mv);
+        // The table outlives the search, so nothing found after a stop may go into it
+        if !*running {
+            return 0;
+        }
+
+        if new_value > value {
+            value = new_value;
+            best_move = entry.mv;
+        }
+
+        if value > alpha {
+            alpha = value;
+        }
+
+        if alpha >= beta {
+            let flag =
+                if value <= alpha_orig {'U'}
+                else if value >= beta {'L'}
+                else{'E'};
+
+            let new_entry =
+            TTEntry{
+                depth: (depth - ply) as u8,
+                flag,
+                value: value_to_tt(value, ply),
+                mv: entry.mv,
+            };
+            tt.insert(hash, new_entry);
+            return value;
+        }
     }
-    for mv in moves {
+
+    for &mv in moves.iter() {
         node.make_move(mv);
-        let new_value = -alphabeta_full_tt(node, depth, ply + 1, -beta, -alpha, nodes_searched, stop_at, tt, running);
+        let new_value = -alphabeta_full_tt(node, depth, ply + 1, -beta, -alpha, stats, deadline, tt, running);
         node.undo_move(mv);
+        if !*running {
+            return 0;
+        }
 
         if new_value > value {
             value = new_value;
@@ -309,101 +324,191 @@ fn alphabeta_full_tt (
     let new_entry =
     TTEntry{
         depth: (depth - ply) as u8,
-        flag: flag,
-        value: value ,
+        flag,
+        value: value_to_tt(value, ply),
         mv: best_move,
     };
-    tt.insert(*node, new_entry);
+    tt.insert(hash, new_entry);
     value
 }
 
-fn get_move_full_tt(request: SearchRequest) -> SearchResult{ 
-    let thinking_time = request.time_left.unwrap_or(Duration::from_secs(10000));
+// Searches every root move, the one in the TT first, and returns them from best to worst with
+// their variations. Only the best multi_pv get exact values: the others are searched just far
+// enough to know they can't beat those.
+#[allow(clippy::too_many_arguments)]
+fn search_root_full_tt(
+    board: &mut Board,
+    depth: usize,
+    multi_pv: usize,
+    stats: &mut SearchStats,
+    deadline: &Deadline,
+    tt: &mut TranspositionTable,
+    running: &mut bool,
+) -> Vec<(isize, Vec<Move>)> {
+    stats.visit(0);
+    let mut moves = MoveList::new();
+    board.generate_moves(&mut moves);
+    if let Some(index) = tt.get(board.get_hash()).and_then(|entry| moves.iter().position(|&x| x == entry.mv)) {
+        moves[..=index].rotate_right(1);
+    }
 
+    let mut lines: Vec<(isize, Vec<Move>)> = vec![];
+    for &mv in moves.iter() {
+        let alpha = if lines.len() >= multi_pv { lines[multi_pv - 1].0 } else { -Score::WIN_VALUE };
+        board.make_move(mv);
+        let value = -alphabeta_full_tt(board, depth, 1, -Score::WIN_VALUE, -alpha, stats, deadline, tt, running);
+        // Read right away, before other moves overwrite the entries of this line
+        let mut pv = vec![mv];
+        pv.extend(get_tt_pv(*board, depth - 1, |x| tt.get(x.get_hash()).map(|entry| entry.mv)));
+        board.undo_move(mv);
+        if !*running {
+            break;
+        }
 
-    let current_time = Instant::now();
-    let limit_time = current_time.add(thinking_time);
+        let index = lines.partition_point(|x| x.0 >= value);
+        lines.insert(index, (value, pv));
+    }
+
+    if let (true, Some((value, pv))) = (*running, lines.first()) {
+        tt.insert(board.get_hash(), TTEntry {
+            depth: depth as u8,
+            flag: 'E',
+            value: *value,
+            mv: pv[0],
+        });
+    }
+    lines
+}
 
-    let mut board = Board {
-        blocks: request.position.blocks,
-        workers: request.position.workers,
-        turn: request.position.turn,
+fn get_move_full_tt(request: SearchRequest, tt: &mut TranspositionTable) -> SearchResult{ 
+    let current_time = Instant::now();
+    let deadline = Deadline {
+        stop_at: request.time_left.map(|x| current_time.add(x)),
+        max_nodes: request.max_nodes,
+        control: request.control.clone(),
     };
-    let mut tt: HashMap<Board, TTEntry> = HashMap::new();
+    // The first iteration always finishes so there is a move to play
+    let no_deadline = Deadline::default();
+
+    let mut board = Board::new(request.position.blocks, request.position.workers, request.position.turn);
+    tt.new_search();
     let mut depth = 0;
-    let mut best_score = -BIG_ENOUGH_VALUE;
+    let mut depth_searched = 0;
+    let mut best_score = -Score::WIN_VALUE;
+    let mut lines = vec![];
     let mut running = true;
 
-    let mut nodes_searched:usize = 0;
+    let mut stats = SearchStats::default();
     while running {
         depth += 1;
         if request.debug{
             print_with_timestamp(&format!("Starting depth: {}", depth));
         }
-        let result = alphabeta_full_tt(&mut board, depth, 0, -BIG_ENOUGH_VALUE, BIG_ENOUGH_VALUE, &mut nodes_searched, limit_time, &mut tt, &mut running);
-        if result > best_score{
-            best_score = result;
+        let result = search_root_full_tt(&mut board, depth, request.multi_pv, &mut stats, if depth == 1 { &no_deadline } else { &deadline }, tt, &mut running);
+        // Values of unfinished iterations are meaningless
+        if running {
+            let (score, pv) = &result[0];
+            if *score > best_score {
+                best_score = *score;
+            }
+            depth_searched = depth;
+            if let Some(sink) = &request.sink {
+                sink.send(&stats.get_info(depth, *score, current_time.elapsed(), convert_pv(&board, pv), Some(tt.get_hashfull())));
+            }
+            lines = result;
         }
 
-        if depth == request.max_depth{
+        if depth >= request.max_depth {
             running = false;
         } 
     }
     let end_time = Instant::now();
     let time_spent_thinking = end_time - current_time;
 
-    let entry= tt.get(&board).unwrap();
+    let pv = &lines[0].1;
 
     let best_score = best_score;
 
     if request.debug{
-        print_with_timestamp(&format!("Best move: {:?} Score: {} Depth: {}", entry.mv, best_score, depth));
+        print_with_timestamp(&format!("Best move: {:?} Score: {} Depth: {}", pv[0], best_score, depth));
     }
 
     SearchResult {
-        mv: convert_move(board, entry.mv),
-        eval: Some(best_score),
-        pv: None,
+        mv: convert_move(&board, pv[0]),
+        eval: Some(Score::from_value(best_score)),
+        pv: Some(convert_pv(&board, pv)),
         time_spent: Some(time_spent_thinking),
-        depth_searched: Some(depth),
+        depth_searched: Some(depth_searched),
+        root_moves: lines
+            .iter()
+            .take(request.multi_pv)
+            .map(|(value, pv)| RootMoveResult {
+                mv: convert_move(&board, pv[0]),
+                eval: Score::from_value(*value),
+                pv: convert_pv(&board, pv),
+            })
+            .collect(),
     }
     
 }
 
 
 
-pub fn get_best_move(request: SearchRequest) -> SearchResult{
-    get_move_full_tt(request)
+// Value of the position for the side to move after a fixed depth search, or None if the
+// deadline passed before the search finished
+pub fn get_position_value(position: Board, depth: usize, deadline: &Deadline, tt: &mut TranspositionTable) -> Option<isize> {
+    let mut board = position;
+    let mut running = true;
+    let mut stats = SearchStats::default();
+
+    let value = alphabeta_full_tt(&mut board, depth, 0, -Score::WIN_VALUE, Score::WIN_VALUE, &mut stats, deadline, tt, &mut running);
+
+    if running {
+        Some(value)
+    } else {
+        None
+    }
+}
+
+pub fn get_best_move(request: SearchRequest, tt: &mut TranspositionTable) -> SearchResult{
+    get_move_full_tt(request, tt)
 }
 
 #[cfg(test)]
 mod tests {
+    use std::sync::Arc;
+
     use super::*;
     use crate::helpers::squares::*;
+    use crate::helpers::turn::*;
 
     fn get_best_move_test(board:Board, depth:usize) -> Move{
         let request = SearchRequest{
             position:board,
             max_depth: depth,
             time_left: None,
+            max_nodes: None,
+            multi_pv: 1,
+            control: Arc::default(),
+            sink: None,
             debug: false,
         };
-        let mv = get_best_move(request).mv;
-        Move{from: mv.from, to:mv.to, build: mv.build.unwrap_or(mv.from)}
+        let mv = get_best_move(request, &mut TranspositionTable::new(1)).mv;
+        Move{from: mv.from.into(), to:mv.to.into(), build: mv.at.unwrap_or(mv.from).into()}
     }
 
     #[test]
     fn m1(){
         let board = 
-            Board {
-                blocks: [0, 0, 0, 0, 0,
-                         0, 3, 0, 0, 0,
-                         0, 2, 0, 0, 0,
-                         0, 0, 0, 0, 0,
-                         0, 0, 0, 0, 0],
-                workers: [C3, C2, C4, B3],
-                turn: W,
-            };
+            Board::new(
+                [0, 0, 0, 0, 0,
+                 0, 3, 0, 0, 0,
+                 0, 2, 0, 0, 0,
+                 0, 0, 0, 0, 0,
+                 0, 0, 0, 0, 0],
+                [C3, C2, C4, B3],
+                W,
+            );
         let depth = 1;
         let best_move = Move {from: C2, to:B2, build: C2};
         assert_eq!(get_best_move_test(board, depth), best_move);
@@ -411,15 +516,15 @@ mod tests {
     #[test]
     fn m1_5(){
         let board = 
-            Board {
-                blocks: [0, 0, 0, 0, 0,
-                         0, 0, 0, 2, 2,
-                         0, 0, 0, 0, 0,
-                         0, 0, 0, 2, 1,
-                         0, 0, 0, 3, 0],
-                workers: [C3, A4, A5, E5],
-                turn: W,
-            };
+            Board::new(
+                [0, 0, 0, 0, 0,
+                 0, 0, 0, 2, 2,
+                 0, 0, 0, 0, 0,
+                 0, 0, 0, 2, 1,
+                 0, 0, 0, 3, 0],
+                [C3, A4, A5, E5],
+                W,
+            );
         let depth = 2;
         let best_move = Move {from: C3, to:C4, build: D5};
         assert_eq!(get_best_move_test(board, depth), best_move);
@@ -427,15 +532,15 @@ mod tests {
     #[test]
     fn prevent_m1(){
         let board = 
-            Board {
-                blocks: [0, 0, 0, 0, 0,
-                         0, 3, 0, 0, 0,
-                         0, 2, 0, 0, 0,
-                         0, 0, 0, 0, 0,
-                         0, 0, 0, 0, 0],
-                workers: [D1, E5, C2, D2],
-                turn: W,
-            };
+            Board::new(
+                [0, 0, 0, 0, 0,
+                 0, 3, 0, 0, 0,
+                 0, 2, 0, 0, 0,
+                 0, 0, 0, 0, 0,
+                 0, 0, 0, 0, 0],
+                [D1, E5, C2, D2],
+                W,
+            );
         let depth = 2;
         let best_move = Move {from: D1, to:C1, build: B2};
         assert_eq!(get_best_move_test(board, depth), best_move);
@@ -443,15 +548,15 @@ mod tests {
     #[test]
     fn mi2_dw (){
         let board = 
-            Board {
-                blocks: [0, 0, 0, 0, 0,
-                         0, 0, 1, 0, 0,
-                         0, 0, 2, 0, 0,
-                         0, 0, 2, 3, 0,
-                         0, 0, 0, 0, 0],
-                workers: [B3, C2, D5, E5],
-                turn: W,
-            };
+            Board::new(
+                [0, 0, 0, 0, 0,
+                 0, 0, 1, 0, 0,
+                 0, 0, 2, 0, 0,
+                 0, 0, 2, 3, 0,
+                 0, 0, 0, 0, 0],
+                [B3, C2, D5, E5],
+                W,
+            );
         let depth = 3;
         let best_move = Move {from: B3, to:C3, build: D3};
         assert_eq!(get_best_move_test(board, depth), best_move);
@@ -460,15 +565,15 @@ mod tests {
     #[test]
     fn mi2_dw_blue (){
         let board = 
-            Board {
-                blocks: [0, 0, 0, 0, 0,
-                         0, 0, 1, 0, 0,
-                         0, 0, 2, 0, 0,
-                         0, 0, 2, 3, 0,
-                         0, 0, 0, 0, 0],
-                workers: [D5, E5, B3, C2],
-                turn: U,
-            };
+            Board::new(
+                [0, 0, 0, 0, 0,
+                 0, 0, 1, 0, 0,
+                 0, 0, 2, 0, 0,
+                 0, 0, 2, 3, 0,
+                 0, 0, 0, 0, 0],
+                [D5, E5, B3, C2],
+                U,
+            );
         let depth = 3;
         let best_move = Move {from: B3, to:C3, build: D3};
         assert_eq!(get_best_move_test(board, depth), best_move);
@@ -477,15 +582,15 @@ mod tests {
     #[test]
     fn mi2_fa (){
         let board = 
-            Board {
-                blocks: [0, 0, 0, 0, 2,
-                         0, 0, 0, 2, 2,
-                         0, 0, 2, 0, 0,
-                         0, 0, 0, 0, 0,
-                         0, 0, 0, 0, 0],
-                workers: [C3, A4, C4, B3],
-                turn: W,
-            };
+            Board::new(
+                [0, 0, 0, 0, 2,
+                 0, 0, 0, 2, 2,
+                 0, 0, 2, 0, 0,
+                 0, 0, 0, 0, 0,
+                 0, 0, 0, 0, 0],
+                [C3, A4, C4, B3],
+                W,
+            );
         let depth = 3;
         let best_move = Move {from: C3, to:B4, build: A5};
         assert_eq!(get_best_move_test(board, depth), best_move);
@@ -493,15 +598,15 @@ mod tests {
     #[test]
     fn m2_5 (){
         let board = 
-            Board {
-                blocks: [0, 0, 0, 0, 0,
-                         0, 0, 0, 0, 0,
-                         0, 0, 0, 4, 1,
-                         4, 4, 0, 2, 0,
-                         4, 0, 1, 3, 0],
-                workers: [C5, D3, E2, D5],
-                turn: W,
-            };
+            Board::new(
+                [0, 0, 0, 0, 0,
+                 0, 0, 0, 0, 0,
+                 0, 0, 0, 4, 1,
+                 4, 4, 0, 2, 0,
+                 4, 0, 1, 3, 0],
+                [C5, D3, E2, D5],
+                W,
+            );
         let depth = 4;
         let best_move = Move {from: C5, to:D4, build: E3};
         assert_eq!(get_best_move_test(board, depth), best_move);
@@ -509,33 +614,61 @@ mod tests {
     #[test]
     fn stalling (){
         let board = 
-            Board {
-                blocks: [0, 0, 0, 0, 0,
-                         0, 2, 3, 0, 0,
-                         0, 2, 0, 0, 0,
-                         2, 3, 0, 0, 0,
-                         0, 0, 0, 0, 0],
-                workers: [B1, D5, A2, B2],
-                turn: W,
-            };
+            Board::new(
+                [0, 0, 0, 0, 0,
+                 0, 2, 3, 0, 0,
+                 0, 2, 0, 0, 0,
+                 2, 3, 0, 0, 0,
+                 0, 0, 0, 0, 0],
+                [B1, D5, A2, B2],
+                W,
+            );
         let depth = 4;
         let best_move = Move {from: D5, to:C4, build: B3};
         assert_eq!(get_best_move_test(board, depth), best_move);
     }
     #[test]
     fn evaluation(){
-        let board = Board{
-            blocks: [1, 4, 0, 3, 2,
-                    3, 0, 0, 2, 3,
-                    4, 0, 0, 0, 2,
-                    0, 0, 4, 0, 0,
-                    2, 0, 1, 1, 0],
-            workers: [C3, C2, B2, D2],
-            turn: W,
-        };
+        let board = Board::new(
+            [1, 4, 0, 3, 2,
+            3, 0, 0, 2, 3,
+            4, 0, 0, 0, 2,
+            0, 0, 4, 0, 0,
+            2, 0, 1, 1, 0],
+            [C3, C2, B2, D2],
+            W,
+        );
         let depth = 6;
         let best_move = Move{from: 12, to:18, build:19};
         assert_eq!(get_best_move_test(board, depth), best_move);
 
     }
-}
\ No newline at end of file
+    #[test]
+    fn win_distance_through_tt (){
+        // Lines reach the same positions at different plies, so TT values have to be moved to
+        // the ply they are read at for the win to stay 3 plies away
+        let board = 
+            Board::new(
+                [0, 0, 0, 0, 0,
+                 0, 0, 1, 0, 0,
+                 0, 0, 2, 0, 0,
+                 0, 0, 2, 3, 0,
+                 0, 0, 0, 0, 0],
+                [B3, C2, D5, E5],
+                W,
+            );
+        for depth in [3, 5] {
+            let request = SearchRequest{
+                position:board,
+                max_depth: depth,
+                time_left: None,
+                max_nodes: None,
+                multi_pv: 1,
+                control: Arc::default(),
+                sink: None,
+                debug: false,
+            };
+            assert_eq!(get_best_move(request, &mut TranspositionTable::new(1)).eval, Some(Score::Win(3)));
+        }
+    }
+}
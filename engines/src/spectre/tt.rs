@@ -0,0 +1,221 @@
+use crate::core::board_rep::Move;
+
+pub const DEFAULT_SIZE_MB: usize = 16;
+pub const MIN_SIZE_MB: usize = 1;
+pub const MAX_SIZE_MB: usize = 4096;
+// Entries looked at to estimate how full the table is, as UCI engines do for hashfull
+const HASHFULL_SAMPLE: usize = 1000;
+
+#[derive(Clone, Debug)]
+pub struct TTEntry{
+    pub depth: u8,
+    pub flag: char,
+    pub value: isize,
+    pub mv: Move,
+}
+
+// An entry packed in two words: the full hash to tell positions sharing a bucket apart, and
+//     bits  0-15  value
+//     bits 16-23  depth
+//     bits 24-25  flag, 0 for an empty slot
+//     bits 26-31  generation of the search that stored it
+//     bits 32-46  from, to and build squares, 5 bits each
+#[derive(Clone, Copy, Default)]
+struct Slot {
+    key: u64,
+    data: u64,
+}
+impl Slot {
+    fn pack(key: u64, entry: &TTEntry, generation: u8) -> Self {
+        let flag: u64 = match entry.flag {
+            'E' => 1,
+            'U' => 2,
+            'L' => 3,
+            _ => unreachable!(),
+        };
+        let value = entry.value.clamp(i16::MIN as isize, i16::MAX as isize) as i16 as u16 as u64;
+        let mv = entry.mv.from as u64 | (entry.mv.to as u64) << 5 | (entry.mv.build as u64) << 10;
+        Slot {
+            key,
+            data: value | (entry.depth as u64) << 16 | flag << 24 | (generation as u64) << 26 | mv << 32,
+        }
+    }
+
+    fn unpack(&self) -> TTEntry {
+        let flag = match (self.data >> 24) & 0b11 {
+            1 => 'E',
+            2 => 'U',
+            _ => 'L',
+        };
+        let mv = self.data >> 32;
+        TTEntry {
+            depth: (self.data >> 16) as u8,
+            flag,
+            value: self.data as u16 as i16 as isize,
+            mv: Move {
+                from: (mv & 0b11111) as usize,
+                to: (mv >> 5 & 0b11111) as usize,
+                build: (mv >> 10 & 0b11111) as usize,
+            },
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        (self.data >> 24) & 0b11 == 0
+    }
+    fn get_depth(&self) -> u8 {
+        (self.data >> 16) as u8
+    }
+    fn get_generation(&self) -> u8 {
+        (self.data >> 26) as u8 & GENERATION_MASK
+    }
+}
+
+const GENERATION_MASK: u8 = 0b111111;
+
+// The first slot keeps the deepest entry of the current search, the second whatever came last
+type Bucket = [Slot ; 2];
+
+// A transposition table of a fixed size, kept between searches. Entries from earlier searches
+// are told apart by their generation and are the first to be replaced.
+pub struct TranspositionTable {
+    buckets: Vec<Bucket>,
+    generation: u8,
+}
+impl TranspositionTable {
+    pub fn new(size_mb: usize) -> Self {
+        TranspositionTable::try_new(size_mb).expect("The sizes the engine picks itself fit in memory")
+    }
+
+    // The number of buckets is rounded down to a power of two so a hash can be masked into an
+    // index. Sizes out of MIN_SIZE_MB..=MAX_SIZE_MB are refused, as is memory the system won't give.
+    pub fn try_new(size_mb: usize) -> Result<Self, String> {
+        if !(MIN_SIZE_MB..=MAX_SIZE_MB).contains(&size_mb) {
+            return Err(format!("The hash has to be between {} and {} MB!", MIN_SIZE_MB, MAX_SIZE_MB));
+        }
+        let bytes = size_mb.checked_mul(1 << 20).ok_or(format!("{} MB don't fit in memory!", size_mb))?;
+        let fits = bytes / std::mem::size_of::<Bucket>();
+        let len = if fits == 0 { 1 } else { 1 << fits.ilog2() };
+
+        let mut buckets = Vec::new();
+        buckets.try_reserve_exact(len).map_err(|_| format!("Couldn't allocate {} MB for the hash!", size_mb))?;
+        buckets.resize(len, Default::default());
+        Ok(TranspositionTable {
+            buckets,
+            generation: 0,
+        })
+    }
+
+    // Called before every search so the entries it stores can be told from older ones
+    pub fn new_search(&mut self) {
+        self.generation = (self.generation + 1) & GENERATION_MASK;
+    }
+
+    fn get_bucket(&self, hash: u64) -> usize {
+        hash as usize & (self.buckets.len() - 1)
+    }
+
+    pub fn get(&self, hash: u64) -> Option<TTEntry> {
+        self.buckets[self.get_bucket(hash)]
+            .iter()
+            .find(|slot| !slot.is_empty() && slot.key == hash)
+            .map(|slot| slot.unpack())
+    }
+
+    pub fn insert(&mut self, hash: u64, entry: TTEntry) {
+        let generation = self.generation;
+        let index = self.get_bucket(hash);
+        let bucket = &mut self.buckets[index];
+        let slot = Slot::pack(hash, &entry, generation);
+
+        let preferred = &bucket[0];
+        if preferred.is_empty() || preferred.key == hash || preferred.get_generation() != generation || entry.depth >= preferred.get_depth() {
+            // What the deep entry is pushed out by still gets a chance in the other slot
+            if !preferred.is_empty() && preferred.key != hash {
+                bucket[1] = bucket[0];
+            }
+            bucket[0] = slot;
+        } else {
+            bucket[1] = slot;
+        }
+    }
+
+    // Permille of the sampled slots holding an entry of the current search
+    pub fn get_hashfull(&self) -> usize {
+        let sample: Vec<&Slot> = self.buckets.iter().flatten().take(HASHFULL_SAMPLE).collect();
+        let used = sample
+            .iter()
+            .filter(|slot| !slot.is_empty() && slot.get_generation() == self.generation)
+            .count();
+        used * 1000 / sample.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::helpers::squares::*;
+
+    fn entry(depth: u8, value: isize) -> TTEntry {
+        TTEntry {
+            depth,
+            flag: 'L',
+            value,
+            mv: Move { from: E5, to: D4, build: C3 },
+        }
+    }
+
+    #[test]
+    fn packing() {
+        let mut tt = TranspositionTable::new(1);
+        assert_eq!(tt.buckets.len(), 1 << 15);
+
+        tt.insert(42, entry(7, -9997));
+        let found = tt.get(42).unwrap();
+        assert_eq!((found.depth, found.flag, found.value, found.mv), (7, 'L', -9997, Move { from: E5, to: D4, build: C3 }));
+        assert!(tt.get(43).is_none());
+        assert!(tt.get(42 + (1 << 15)).is_none());
+    }
+
+    #[test]
+    fn sizes() {
+        assert!(TranspositionTable::try_new(0).is_err());
+        assert!(TranspositionTable::try_new(MAX_SIZE_MB + 1).is_err());
+        assert!(TranspositionTable::try_new(usize::MAX).is_err());
+        assert_eq!(TranspositionTable::try_new(2).unwrap().buckets.len(), 1 << 16);
+    }
+
+    #[test]
+    fn replacement() {
+        let mut tt = TranspositionTable::new(1);
+        let other = 1 << 15;
+        let third = 2 << 15;
+
+        // A shallower entry doesn't push out a deeper one of the same search
+        tt.insert(0, entry(5, 1));
+        tt.insert(other, entry(2, 2));
+        assert_eq!(tt.get(0).unwrap().value, 1);
+        assert_eq!(tt.get(other).unwrap().value, 2);
+        tt.insert(third, entry(1, 3));
+        assert_eq!(tt.get(0).unwrap().value, 1);
+        assert!(tt.get(other).is_none());
+
+        // Entries of an earlier search are replaced whatever their depth
+        tt.new_search();
+        tt.insert(other, entry(1, 4));
+        assert_eq!(tt.get(other).unwrap().value, 4);
+        assert_eq!(tt.get(0).unwrap().value, 1);
+    }
+
+    #[test]
+    fn hashfull() {
+        let mut tt = TranspositionTable::new(1);
+        assert_eq!(tt.get_hashfull(), 0);
+        for hash in 0..250 {
+            tt.insert(hash, entry(1, 0));
+        }
+        assert_eq!(tt.get_hashfull(), 250);
+        tt.new_search();
+        assert_eq!(tt.get_hashfull(), 0);
+    }
+}
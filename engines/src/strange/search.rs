@@ -1,56 +1,19 @@
 use std::ops::Add;
-use std::time::Duration;
 use std::time::Instant;
 
-use crate::BenchmarkRequest;
-use crate::helpers::print_with_timestamp;
-use crate::helpers::turn::*;
-use crate::strange::board_rep::*;
-use crate::strange::eval::*;
-use crate::models::SearchResult;
+use crate::helpers::{get_loss_value, print_with_timestamp, Deadline, SearchStats};
+use crate::core::board_rep::*;
+use crate::core::eval::*;
+use crate::models::{Request, RootMoveResult, Score, SearchResult};
 
-use super::convert_move;
+use crate::core::{convert_move, convert_pv};
+use crate::core::search::{SearchRequest, CHECK_CLOCK_EVERY};
 
-const BIG_ENOUGH_VALUE:isize = 10000;
-const CHECK_CLOCK_EVERY:usize = 1000;
-
-pub struct SearchRequest{
-    pub position:Board,
-    pub max_depth:usize,
-    pub time_left:Option<Duration>,
-    pub debug: bool,
-}
-
-fn prepare_to_benchmark() -> impl Fn(BenchmarkRequest) -> SearchResult {
-    move |benchmark_request| {
-        let internal_board = Board {
-            blocks: benchmark_request.position.blocks,
-            workers: benchmark_request.position.workers,
-            turn: benchmark_request.position.turn,
-            moves: vec![],
-        };
-
-        let request = SearchRequest {
-            position: internal_board,
-            max_depth: benchmark_request.max_depth,
-            time_left: None,
-            debug: true,
-        };
-        get_move(request)
-    }
-}
-pub fn strange_v1_benchmark(br:BenchmarkRequest)-> SearchResult{
-    prepare_to_benchmark()(br)
+pub fn strange_v1_benchmark(request:Request)-> SearchResult{
+    get_move(SearchRequest::from_request(request, true))
 }
-    
-fn get_color(node:&Board) -> isize{
-    match node.turn {
-        W => 1,
-        U => -1,
-        _ => unreachable!(),
-    }
-} 
 
+#[allow(clippy::too_many_arguments)]
 fn alphabeta_id (
     node:&mut Board,
     depth:usize,
@@ -58,72 +21,63 @@ fn alphabeta_id (
     mut alpha:isize,
     beta:isize,
     last_pv: Vec<Move>,
-    nodes_searched: &mut usize, 
-    stop_at: Instant,
+    stats: &mut SearchStats, 
+    deadline: &Deadline,
     in_pv: bool,
     running:&mut bool,
 )-> (isize, Vec<Move>){
-    *nodes_searched += 1;
-    if (! *running) || ((*nodes_searched % CHECK_CLOCK_EVERY == 0) && Instant::now() > stop_at) {
+    stats.visit(ply);
+    if (! *running) || deadline.out_of_nodes(stats.nodes) || (stats.nodes.is_multiple_of(CHECK_CLOCK_EVERY) && deadline.should_stop(stats.nodes)) {
         *running = false;
         return (0, vec![]);
     }
-    let color = get_color(node);
+    let color = node.get_color();
 
-    match node.moves.last() {
-        Some(last) => {
-            if node.blocks[last.to] == 3 {
-                return (-BIG_ENOUGH_VALUE - (depth - ply) as isize, vec![]);
-            }
-        }
-        None => {}
-    }   
+    if node.game_is_over() {
+        return (get_loss_value(ply), vec![]);
+    }
     if ply == depth{
         return (color * eval(node), vec![]);  
     }
-    let mut value = -BIG_ENOUGH_VALUE * 100;
+    let mut value = -Score::WIN_VALUE * 100;
     let mut pv:Vec<Move> = vec![];
+    // The previous variation can be shorter than the current depth when it ended in a win
     let previous_best_move =
-        if depth == 1 || (ply+1 == depth) || in_pv == false{
-            None
+        if in_pv && ply+1 < depth{
+            last_pv.get(ply).copied()
         }
         else{
-            Some(last_pv[ply])
+            None
         };
 
-    match previous_best_move{
-        Some (mv) => {
-            node.make_move(mv);
-            let result = alphabeta_id(node, depth, ply+1, -beta, -alpha, last_pv.clone(), nodes_searched, stop_at, true, running);
-            let new_value = -result.0;
-            node.undo_move(mv);
+    if let Some (mv) = previous_best_move {
+        node.make_move(mv);
+        let result = alphabeta_id(node, depth, ply+1, -beta, -alpha, last_pv.clone(), stats, deadline, true, running);
+        let new_value = -result.0;
+        node.undo_move(mv);
 
-            if new_value > value{
-                value = new_value;
-                pv = vec![mv];
-                pv.extend(result.1);
-            }
-            if value > alpha{
-                alpha = value;
-            }
-            if alpha >= beta{
-                return (value, pv);
-            }
+        if new_value > value{
+            value = new_value;
+            pv = vec![mv];
+            pv.extend(result.1);
+        }
+        if value > alpha{
+            alpha = value;
+        }
+        if alpha >= beta{
+            return (value, pv);
         }
-        None =>(),
     }
-    let moves = node.generate_moves();
+    let mut moves = MoveList::new();
+    node.generate_moves(&mut moves);
 
-    if moves.len() == 0{
-        return (-BIG_ENOUGH_VALUE - (depth - ply) as isize, vec![]);
+    if moves.is_empty(){
+        return (get_loss_value(ply), vec![]);
     }
-    for mv in moves{
-        match previous_best_move{
-            Some(m) => if mv == m{continue;}
-            None => ()
-        }
+    for &mv in moves.iter() {
+        if let Some(m) = previous_best_move { if mv == m{continue;} }
         node.make_move(mv);
-        let result = alphabeta_id(node, depth, ply+1, -beta, -alpha, last_pv.clone(), nodes_searched, stop_at, false, running);
+        let result = alphabeta_id(node, depth, ply+1, -beta, -alpha, last_pv.clone(), stats, deadline, false, running);
         let new_value = -(result.0);
         node.undo_move(mv);
         if new_value > value{
@@ -142,33 +96,80 @@ fn alphabeta_id (
 
 }
 
-fn get_move(request: SearchRequest) -> SearchResult{ 
-    let thinking_time = request.time_left.unwrap_or(Duration::from_secs(10000));
+// Searches every root move, the previous best first, and returns them from best to worst with
+// their variations. Only the best multi_pv get exact values: the others are searched just far
+// enough to know they can't beat those.
+fn search_root(
+    board: &mut Board,
+    depth: usize,
+    multi_pv: usize,
+    last_pv: &[Move],
+    stats: &mut SearchStats,
+    deadline: &Deadline,
+    running: &mut bool,
+) -> Vec<(isize, Vec<Move>)> {
+    stats.visit(0);
+    let mut moves = MoveList::new();
+    board.generate_moves(&mut moves);
+    if let Some(index) = last_pv.first().and_then(|&first| moves.iter().position(|&x| x == first)) {
+        moves[..=index].rotate_right(1);
+    }
 
+    let mut lines: Vec<(isize, Vec<Move>)> = vec![];
+    for &mv in moves.iter() {
+        let alpha = if lines.len() >= multi_pv { lines[multi_pv - 1].0 } else { -Score::WIN_VALUE };
+        let in_pv = last_pv.first() == Some(&mv);
+        board.make_move(mv);
+        let result = alphabeta_id(board, depth, 1, -Score::WIN_VALUE, -alpha, last_pv.to_vec(), stats, deadline, in_pv, running);
+        board.undo_move(mv);
+        if !*running {
+            break;
+        }
 
-    let current_time = Instant::now();
-    let limit_time = current_time.add(thinking_time);
+        let value = -result.0;
+        let mut pv = vec![mv];
+        pv.extend(result.1);
+        let index = lines.partition_point(|x| x.0 >= value);
+        lines.insert(index, (value, pv));
+    }
+    lines
+}
 
-    let mut board = Board {
-        blocks: request.position.blocks,
-        workers: request.position.workers,
-        turn: request.position.turn,
-        moves: vec![],
+fn get_move(request: SearchRequest) -> SearchResult{ 
+    let current_time = Instant::now();
+    let deadline = Deadline {
+        stop_at: request.time_left.map(|x| current_time.add(x)),
+        max_nodes: request.max_nodes,
+        control: request.control.clone(),
     };
+    // The first iteration always finishes so there is a move to play
+    let no_deadline = Deadline::default();
+
+    let mut board = Board::new(request.position.blocks, request.position.workers, request.position.turn);
+    let mut lines: Vec<(isize, Vec<Move>)> = vec![];
     let mut pv:Vec<Move> = vec![];
     let mut depth = 0;
-    let mut best_score = -BIG_ENOUGH_VALUE;
+    let mut depth_searched = 0;
+    let mut best_score = -Score::WIN_VALUE;
     let mut running = true;
-    let mut nodes_searched = 0;
+    let mut stats = SearchStats::default();
     while running {
         depth += 1;
         if request.debug{
             print_with_timestamp(&format!("Starting depth: {}", depth));
         }
-        let result = alphabeta_id(&mut board, depth, 0, -BIG_ENOUGH_VALUE, BIG_ENOUGH_VALUE, pv.clone(), &mut nodes_searched, limit_time, true, &mut running);
-        pv = result.1;
-        best_score = result.0;
-        if depth == request.max_depth {
+        let result = search_root(&mut board, depth, request.multi_pv, &pv, &mut stats, if depth == 1 { &no_deadline } else { &deadline }, &mut running);
+        // An unfinished iteration only has part of a variation
+        if let (true, Some((score, line))) = (running, result.first()) {
+            pv = line.clone();
+            best_score = *score;
+            lines = result;
+            depth_searched = depth;
+            if let Some(sink) = &request.sink {
+                sink.send(&stats.get_info(depth, best_score, current_time.elapsed(), convert_pv(&board, &pv), None));
+            }
+        }
+        if depth >= request.max_depth {
             running = false;
         } 
     }
@@ -185,11 +186,20 @@ fn get_move(request: SearchRequest) -> SearchResult{
     }
 
     SearchResult {
-        mv: convert_move(board, best_move),
-        eval: Some(best_score),
-        pv: None,
+        mv: convert_move(&board, best_move),
+        eval: Some(Score::from_value(best_score)),
+        pv: Some(convert_pv(&board, &pv)),
         time_spent: Some(time_spent_thinking),
-        depth_searched: Some(depth),
+        depth_searched: Some(depth_searched),
+        root_moves: lines
+            .iter()
+            .take(request.multi_pv)
+            .map(|(value, pv)| RootMoveResult {
+                mv: convert_move(&board, pv[0]),
+                eval: Score::from_value(*value),
+                pv: convert_pv(&board, pv),
+            })
+            .collect(),
     }
     
 }
@@ -200,33 +210,39 @@ pub fn get_best_move(request: SearchRequest) -> SearchResult{
 
 #[cfg(test)]
 mod tests {
+    use std::sync::Arc;
+
     use super::*;
     use crate::helpers::squares::*;
+    use crate::helpers::turn::*;
 
     fn get_best_move_test(board:Board, depth:usize) -> Move{
         let request = SearchRequest{
             position:board,
             max_depth: depth,
             time_left: None,
+            max_nodes: None,
+            multi_pv: 1,
+            control: Arc::default(),
+            sink: None,
             debug: false,
         };
         let mv = get_best_move(request).mv;
-        Move{from: mv.from, to:mv.to, build: mv.build.unwrap_or(mv.from)}
+        Move{from: mv.from.into(), to:mv.to.into(), build: mv.at.unwrap_or(mv.from).into()}
     }
 
     #[test]
     fn m1(){
         let board = 
-            Board {
-                blocks: [0, 0, 0, 0, 0,
-                         0, 3, 0, 0, 0,
-                         0, 2, 0, 0, 0,
-                         0, 0, 0, 0, 0,
-                         0, 0, 0, 0, 0],
-                workers: [C3, C2, C4, B3],
-                turn: W,
-                moves: vec![],
-            };
+            Board::new(
+                [0, 0, 0, 0, 0,
+                 0, 3, 0, 0, 0,
+                 0, 2, 0, 0, 0,
+                 0, 0, 0, 0, 0,
+                 0, 0, 0, 0, 0],
+                [C3, C2, C4, B3],
+                W,
+            );
         let depth = 1;
         let best_move = Move {from: C2, to:B2, build: C2};
         assert_eq!(get_best_move_test(board, depth), best_move);
@@ -234,16 +250,15 @@ mod tests {
     #[test]
     fn m1_5(){
         let board = 
-            Board {
-                blocks: [0, 0, 0, 0, 0,
-                         0, 0, 0, 2, 2,
-                         0, 0, 0, 0, 0,
-                         0, 0, 0, 2, 1,
-                         0, 0, 0, 3, 0],
-                workers: [C3, A4, A5, E5],
-                turn: W,
-                moves: vec![],
-            };
+            Board::new(
+                [0, 0, 0, 0, 0,
+                 0, 0, 0, 2, 2,
+                 0, 0, 0, 0, 0,
+                 0, 0, 0, 2, 1,
+                 0, 0, 0, 3, 0],
+                [C3, A4, A5, E5],
+                W,
+            );
         let depth = 2;
         let best_move = Move {from: C3, to:C4, build: D5};
         assert_eq!(get_best_move_test(board, depth), best_move);
@@ -251,16 +266,15 @@ mod tests {
     #[test]
     fn prevent_m1(){
         let board = 
-            Board {
-                blocks: [0, 0, 0, 0, 0,
-                         0, 3, 0, 0, 0,
-                         0, 2, 0, 0, 0,
-                         0, 0, 0, 0, 0,
-                         0, 0, 0, 0, 0],
-                workers: [D1, E5, C2, D2],
-                turn: W,
-                moves: vec![],
-            };
+            Board::new(
+                [0, 0, 0, 0, 0,
+                 0, 3, 0, 0, 0,
+                 0, 2, 0, 0, 0,
+                 0, 0, 0, 0, 0,
+                 0, 0, 0, 0, 0],
+                [D1, E5, C2, D2],
+                W,
+            );
         let depth = 2;
         let best_move = Move {from: D1, to:C1, build: B2};
         assert_eq!(get_best_move_test(board, depth), best_move);
@@ -268,16 +282,15 @@ mod tests {
     #[test]
     fn mi2_dw (){
         let board = 
-            Board {
-                blocks: [0, 0, 0, 0, 0,
-                         0, 0, 1, 0, 0,
-                         0, 0, 2, 0, 0,
-                         0, 0, 2, 3, 0,
-                         0, 0, 0, 0, 0],
-                workers: [B3, C2, D5, E5],
-                turn: W,
-                moves: vec![],
-            };
+            Board::new(
+                [0, 0, 0, 0, 0,
+                 0, 0, 1, 0, 0,
+                 0, 0, 2, 0, 0,
+                 0, 0, 2, 3, 0,
+                 0, 0, 0, 0, 0],
+                [B3, C2, D5, E5],
+                W,
+            );
         let depth = 3;
         let best_move = Move {from: B3, to:C3, build: D3};
         assert_eq!(get_best_move_test(board, depth), best_move);
@@ -286,16 +299,15 @@ mod tests {
     #[test]
     fn mi2_dw_blue (){
         let board = 
-            Board {
-                blocks: [0, 0, 0, 0, 0,
-                         0, 0, 1, 0, 0,
-                         0, 0, 2, 0, 0,
-                         0, 0, 2, 3, 0,
-                         0, 0, 0, 0, 0],
-                workers: [D5, E5, B3, C2],
-                turn: U,
-                moves: vec![],
-            };
+            Board::new(
+                [0, 0, 0, 0, 0,
+                 0, 0, 1, 0, 0,
+                 0, 0, 2, 0, 0,
+                 0, 0, 2, 3, 0,
+                 0, 0, 0, 0, 0],
+                [D5, E5, B3, C2],
+                U,
+            );
         let depth = 3;
         let best_move = Move {from: B3, to:C3, build: D3};
         assert_eq!(get_best_move_test(board, depth), best_move);
@@ -304,16 +316,15 @@ mod tests {
     #[test]
     fn mi2_fa (){
         let board = 
-            Board {
-                blocks: [0, 0, 0, 0, 2,
-                         0, 0, 0, 2, 2,
-                         0, 0, 2, 0, 0,
-                         0, 0, 0, 0, 0,
-                         0, 0, 0, 0, 0],
-                workers: [C3, A4, C4, B3],
-                turn: W,
-                moves: vec![],
-            };
+            Board::new(
+                [0, 0, 0, 0, 2,
+                 0, 0, 0, 2, 2,
+                 0, 0, 2, 0, 0,
+                 0, 0, 0, 0, 0,
+                 0, 0, 0, 0, 0],
+                [C3, A4, C4, B3],
+                W,
+            );
         let depth = 3;
         let best_move = Move {from: C3, to:B4, build: A5};
         assert_eq!(get_best_move_test(board, depth), best_move);
@@ -321,16 +332,15 @@ mod tests {
     #[test]
     fn m2_5 (){
         let board = 
-            Board {
-                blocks: [0, 0, 0, 0, 0,
-                         0, 0, 0, 0, 0,
-                         0, 0, 0, 4, 1,
-                         4, 4, 0, 2, 0,
-                         4, 0, 1, 3, 0],
-                workers: [C5, D3, E2, D5],
-                turn: W,
-                moves: vec![],
-            };
+            Board::new(
+                [0, 0, 0, 0, 0,
+                 0, 0, 0, 0, 0,
+                 0, 0, 0, 4, 1,
+                 4, 4, 0, 2, 0,
+                 4, 0, 1, 3, 0],
+                [C5, D3, E2, D5],
+                W,
+            );
         let depth = 4;
         let best_move = Move {from: C5, to:D4, build: E3};
         assert_eq!(get_best_move_test(board, depth), best_move);
@@ -338,18 +348,44 @@ mod tests {
     #[test]
     fn stalling (){
         let board = 
-            Board {
-                blocks: [0, 0, 0, 0, 0,
-                         0, 2, 3, 0, 0,
-                         0, 2, 0, 0, 0,
-                         2, 3, 0, 0, 0,
-                         0, 0, 0, 0, 0],
-                workers: [B1, D5, A2, B2],
-                turn: W,
-                moves: vec![],
-            };
+            Board::new(
+                [0, 0, 0, 0, 0,
+                 0, 2, 3, 0, 0,
+                 0, 2, 0, 0, 0,
+                 2, 3, 0, 0, 0,
+                 0, 0, 0, 0, 0],
+                [B1, D5, A2, B2],
+                W,
+            );
         let depth = 4;
         let best_move = Move {from: D5, to:C4, build: B3};
         assert_eq!(get_best_move_test(board, depth), best_move);
     }
-}
\ No newline at end of file
+    #[test]
+    fn win_shortens_pv (){
+        // The variation ends with the win, so deeper iterations run out of moves to follow
+        let board = 
+            Board::new(
+                [0, 0, 0, 0, 0,
+                 0, 3, 0, 0, 0,
+                 0, 2, 0, 0, 0,
+                 0, 0, 0, 0, 0,
+                 0, 0, 0, 0, 0],
+                [C3, C2, C4, B3],
+                W,
+            );
+        let request = SearchRequest{
+            position:board,
+            max_depth: 4,
+            time_left: None,
+            max_nodes: None,
+            multi_pv: 1,
+            control: Arc::default(),
+            sink: None,
+            debug: false,
+        };
+        let result = get_best_move(request);
+        assert_eq!(result.pv.map(|x| x.len()), Some(1));
+        assert_eq!(result.depth_searched, Some(4));
+    }
+}
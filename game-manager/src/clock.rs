@@ -0,0 +1,192 @@
+use engines::*;
+use std::{fmt::Display, str::FromStr, time::Duration};
+
+// How much time the players get, counted in plies so placements use the clock too. Written as
+// "60" for 60 seconds each for the whole game, "60+0.5" to give back half a second after every
+// ply, "40/60" for 60 more seconds every 40 plies, which takes an increment too as "40/60+0.5",
+// and "5/move" for 5 seconds per ply however fast the previous ones were played.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum TimeControl {
+    Clock {
+        base: Duration,
+        increment: Duration,
+        // The base time is added again every time a player has made this many plies
+        moves: Option<usize>,
+    },
+    PerMove(Duration),
+}
+impl TimeControl {
+    pub fn sudden_death(base: Duration) -> Self {
+        TimeControl::Clock {
+            base,
+            increment: Duration::ZERO,
+            moves: None,
+        }
+    }
+}
+
+fn parse_seconds(text: &str) -> Result<Duration, String> {
+    text.parse::<f64>()
+        .ok()
+        .and_then(|x| Duration::try_from_secs_f64(x).ok())
+        .ok_or(format!("{} is not a valid number of seconds", text))
+}
+
+impl FromStr for TimeControl {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Some(time) = s.strip_suffix("/move") {
+            return Ok(TimeControl::PerMove(parse_seconds(time)?));
+        }
+
+        let (moves, rest) = match s.split_once('/') {
+            Some((moves, rest)) => {
+                let moves: usize = moves.parse().map_err(|_| format!("{} is not a valid number of moves", moves))?;
+                if moves == 0 {
+                    return Err(String::from("A period needs at least one move"));
+                }
+                (Some(moves), rest)
+            }
+            None => (None, s),
+        };
+        let (base, increment) = match rest.split_once('+') {
+            Some((base, increment)) => (parse_seconds(base)?, parse_seconds(increment)?),
+            None => (parse_seconds(rest)?, Duration::ZERO),
+        };
+
+        Ok(TimeControl::Clock { base, increment, moves })
+    }
+}
+impl Display for TimeControl {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TimeControl::Clock { base, increment, moves } => {
+                if let Some(moves) = moves {
+                    write!(f, "{}/", moves)?;
+                }
+                write!(f, "{}", base.as_secs_f64())?;
+                if !increment.is_zero() {
+                    write!(f, "+{}", increment.as_secs_f64())?;
+                }
+                Ok(())
+            }
+            TimeControl::PerMove(time) => write!(f, "{}/move", time.as_secs_f64()),
+        }
+    }
+}
+
+// Both players' clocks during a game
+pub struct Clocks {
+    control: TimeControl,
+    left: [Duration ; 2],
+    plies: [usize ; 2],
+}
+impl Clocks {
+    pub fn new(control: TimeControl) -> Self {
+        let left = match control {
+            TimeControl::Clock { base, .. } => base,
+            TimeControl::PerMove(time) => time,
+        };
+        Clocks {
+            control,
+            left: [left, left],
+            plies: [0, 0],
+        }
+    }
+
+    // The most the side can spend on its next ply
+    pub fn get_time_left(&self, turn: Turn) -> Duration {
+        self.left[turn]
+    }
+
+    // What the engine to move is told about the clocks
+    pub fn get_limits(&self, turn: Turn) -> SearchLimits {
+        match self.control {
+            TimeControl::Clock { increment, moves, .. } => SearchLimits {
+                time: self.left.map(Some),
+                increment: [increment, increment],
+                moves_to_go: moves.map(|x| x - self.plies[turn] % x),
+                ..Default::default()
+            },
+            TimeControl::PerMove(time) => SearchLimits::movetime(time),
+        }
+    }
+
+    // Charges a ply to the side's clock, returning false if it took longer than they had
+    pub fn punch(&mut self, turn: Turn, elapsed: Duration) -> bool {
+        if elapsed > self.left[turn] {
+            return false;
+        }
+
+        self.plies[turn] += 1;
+        match self.control {
+            TimeControl::Clock { base, increment, moves } => {
+                self.left[turn] = self.left[turn] - elapsed + increment;
+                if moves.is_some_and(|x| self.plies[turn].is_multiple_of(x)) {
+                    self.left[turn] += base;
+                }
+            }
+            TimeControl::PerMove(time) => self.left[turn] = time,
+        }
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn notation() {
+        for text in ["60", "60+0.5", "40/60", "40/90+1", "5/move", "0.25/move"] {
+            let control: TimeControl = text.parse().unwrap();
+            assert_eq!(control.to_string(), text);
+        }
+        assert_eq!("60".parse(), Ok(TimeControl::sudden_death(Duration::from_secs(60))));
+        assert_eq!("40/60+1".parse(), Ok(TimeControl::Clock {
+            base: Duration::from_secs(60),
+            increment: Duration::from_secs(1),
+            moves: Some(40),
+        }));
+
+        for text in ["", "soon", "-5", "0/60", "40/", "60+", "x/move"] {
+            assert!(text.parse::<TimeControl>().is_err(), "{} should not parse", text);
+        }
+    }
+
+    #[test]
+    fn increment() {
+        let mut clocks = Clocks::new("10+2".parse().unwrap());
+        assert!(clocks.punch(P1, Duration::from_secs(3)));
+        assert_eq!(clocks.get_time_left(P1), Duration::from_secs(9));
+        assert_eq!(clocks.get_time_left(P2), Duration::from_secs(10));
+
+        let limits = clocks.get_limits(P2);
+        assert_eq!(limits.time, [Some(Duration::from_secs(9)), Some(Duration::from_secs(10))]);
+        assert_eq!(limits.increment[P2], Duration::from_secs(2));
+        assert_eq!(limits.moves_to_go, None);
+
+        assert!(!clocks.punch(P2, Duration::from_secs(11)));
+    }
+
+    #[test]
+    fn periods() {
+        let mut clocks = Clocks::new("2/10".parse().unwrap());
+        assert_eq!(clocks.get_limits(P1).moves_to_go, Some(2));
+        assert!(clocks.punch(P1, Duration::from_secs(4)));
+        assert_eq!(clocks.get_limits(P1).moves_to_go, Some(1));
+        assert!(clocks.punch(P1, Duration::from_secs(4)));
+        assert_eq!(clocks.get_time_left(P1), Duration::from_secs(12));
+        assert_eq!(clocks.get_limits(P1).moves_to_go, Some(2));
+    }
+
+    #[test]
+    fn per_move() {
+        let mut clocks = Clocks::new("1/move".parse().unwrap());
+        assert_eq!(clocks.get_limits(P1), SearchLimits::movetime(Duration::from_secs(1)));
+        assert!(clocks.punch(P1, Duration::from_millis(100)));
+        assert_eq!(clocks.get_time_left(P1), Duration::from_secs(1));
+        assert!(!clocks.punch(P1, Duration::from_millis(1100)));
+    }
+}
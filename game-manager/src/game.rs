@@ -1,8 +1,276 @@
 use engines::*;
-use std::time::{Duration, Instant};
+use engines::external::ExternalEngine;
+use engines::handle::SearchHandle;
+use std::{
+    fmt::Display,
+    time::{Duration, Instant},
+};
 
-pub fn run_game(engines: [Box<dyn Engine> ; 2], time: Duration) {
-    let mut board = Board::new([B3, C2], [C4, D3]);
-    let mut times = [time, time];
-    
-}
\ No newline at end of file
+use crate::clock::{Clocks, TimeControl};
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum GameEndReason {
+    // The winner moved a worker up to the third level
+    ReachedThirdLevel,
+    // The loser had no worker able to move on their turn
+    NoLegalMoves,
+    // The loser ran out of time on their clock
+    TimeForfeit,
+    // The loser played a move the rules don't allow
+    IllegalMove(Move, RulesError),
+    // The loser placed their workers where the rules don't allow
+    IllegalPlacement([Square ; 2], RulesError),
+    // The loser's engine panicked while searching
+    EngineCrashed(String),
+}
+
+impl Display for GameEndReason {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            GameEndReason::ReachedThirdLevel => write!(f, "reached the third level"),
+            GameEndReason::NoLegalMoves => write!(f, "no legal moves"),
+            GameEndReason::TimeForfeit => write!(f, "time forfeit"),
+            GameEndReason::IllegalMove(mv, message) => write!(f, "illegal move {}: {}", mv, message),
+            GameEndReason::IllegalPlacement(squares, message) => {
+                write!(f, "illegal placement {} {}: {}", squares[0], squares[1], message)
+            }
+            GameEndReason::EngineCrashed(message) => write!(f, "engine crashed: {}", message),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct GameResult {
+    pub winner: Turn,
+    pub reason: GameEndReason,
+    // Every ply played, with the time it took and the eval the engine reported
+    pub history: GameState,
+    pub time_used: [Duration ; 2],
+}
+
+enum TurnOutcome {
+    Played(Box<dyn Engine>, Ply, Option<Score>),
+    Forfeit(GameEndReason),
+}
+
+// Waits for a search to answer in time, turning what it found into a ply. A search that
+// overruns is told to stop and abandoned.
+fn finish<T: Send + 'static>(handle: SearchHandle<T>, time_left: Duration, to_ply: fn(T) -> (Ply, Option<Score>)) -> TurnOutcome {
+    match handle.wait_timeout(time_left) {
+        Some(Ok((engine, result))) => {
+            let (ply, eval) = to_ply(result);
+            TurnOutcome::Played(engine, ply, eval)
+        }
+        Some(Err(message)) => TurnOutcome::Forfeit(GameEndReason::EngineCrashed(message)),
+        None => {
+            handle.stop();
+            TurnOutcome::Forfeit(GameEndReason::TimeForfeit)
+        }
+    }
+}
+
+// Runs the search on its own thread so a panic can't take the manager down and a
+// hung engine can be abandoned once its clock runs out.
+// Placements are requested while the board is still in its setup phase, moves afterwards.
+fn request_ply(engine: Box<dyn Engine>, board: Board, limits: SearchLimits, time_left: Duration) -> TurnOutcome {
+    if board.in_setup() {
+        let handle = SearchHandle::place(engine, board, limits, None);
+        finish(handle, time_left, |x| (Ply::Place(x.squares), x.eval))
+    } else {
+        let handle = SearchHandle::search(engine, board, limits, None);
+        finish(handle, time_left, |x| (Ply::Move(x.mv), x.eval))
+    }
+}
+
+// Engines are registry names, or "cmd:" followed by the command line of a program speaking the
+// engine protocol, e.g. "cmd:./old/engine-deploy spectre"
+pub fn create_engine(spec: &str) -> Result<Box<dyn Engine>, String> {
+    if let Some(command) = spec.strip_prefix("cmd:") {
+        let mut parts = command.split_whitespace();
+        let program = parts.next().ok_or(format!("No program given in {}", spec))?;
+        let args: Vec<&str> = parts.collect();
+        return ExternalEngine::spawn(program, &args).map(|x| Box::new(x) as Box<dyn Engine>);
+    }
+    get_engine(spec).ok_or(format!("Unknown engine {}! Available: {:?}", spec, get_engine_names()))
+}
+
+pub fn run_game(engines: [Box<dyn Engine> ; 2], start: Board, time: TimeControl) -> GameResult {
+    let mut history = GameState::new(start);
+    let mut engines = engines.map(Some);
+    let mut clocks = Clocks::new(time);
+    let mut time_used = [Duration::ZERO ; 2];
+
+    let (winner, reason) = loop {
+        let board = *history.get_board();
+        let turn = board.get_turn();
+        let engine = engines[turn].take().expect("Engine is only missing after a forfeit");
+
+        let start = Instant::now();
+        let outcome = request_ply(engine, board, clocks.get_limits(turn), clocks.get_time_left(turn));
+        let elapsed = start.elapsed();
+
+        time_used[turn] += elapsed;
+        let in_time = clocks.punch(turn, elapsed);
+
+        let (ply, eval) = match outcome {
+            TurnOutcome::Played(..) if !in_time => break (turn.next(), GameEndReason::TimeForfeit),
+            TurnOutcome::Played(engine, ply, eval) => {
+                engines[turn] = Some(engine);
+                (ply, eval)
+            }
+            TurnOutcome::Forfeit(reason) => break (turn.next(), reason),
+        };
+
+        if let Err(error) = history.play(ply, PlyInfo { time_spent: Some(elapsed), eval }) {
+            let reason = match ply {
+                Ply::Place(squares) => GameEndReason::IllegalPlacement(squares, error),
+                Ply::Move(mv) => GameEndReason::IllegalMove(mv, error),
+            };
+            break (turn.next(), reason);
+        }
+
+        let board = history.get_board();
+        if let Some(winner) = board.get_victory() {
+            let reason = match ply {
+                Ply::Move(mv) if board.get_blocks()[mv.to] == T3 => GameEndReason::ReachedThirdLevel,
+                _ => GameEndReason::NoLegalMoves,
+            };
+            break (winner, reason);
+        }
+    };
+
+    GameResult {
+        winner,
+        reason,
+        history,
+        time_used,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::openings::default_start;
+    use std::thread;
+
+    struct Crasher;
+    impl Engine for Crasher {
+        fn get_info(&self) -> EngineInfo {
+            EngineInfo {
+                name: String::from("crasher"),
+                eval_range: (0, 0),
+            }
+        }
+        fn get_search_result(&self, _: Request) -> SearchResult {
+            panic!("Crasher always crashes");
+        }
+    }
+
+    struct Sleeper;
+    impl Engine for Sleeper {
+        fn get_info(&self) -> EngineInfo {
+            EngineInfo {
+                name: String::from("sleeper"),
+                eval_range: (0, 0),
+            }
+        }
+        fn get_search_result(&self, request: Request) -> SearchResult {
+            thread::sleep(request.get_time_left().unwrap() * 10);
+            unreachable!("Sleeper should have been abandoned");
+        }
+    }
+
+    // Moves the B3 worker onto the opponent's worker in C4
+    struct Cheater;
+    impl Engine for Cheater {
+        fn get_info(&self) -> EngineInfo {
+            EngineInfo {
+                name: String::from("cheater"),
+                eval_range: (0, 0),
+            }
+        }
+        fn get_search_result(&self, _: Request) -> SearchResult {
+            SearchResult {
+                mv: Move { from: B3, to: C4, at: Some(B3) },
+                eval: None,
+                pv: None,
+                depth_searched: None,
+                time_spent: None,
+                root_moves: vec![],
+            }
+        }
+    }
+
+    // Places both workers on C3
+    struct Stacker;
+    impl Engine for Stacker {
+        fn get_info(&self) -> EngineInfo {
+            EngineInfo {
+                name: String::from("stacker"),
+                eval_range: (0, 0),
+            }
+        }
+        fn get_search_result(&self, _: Request) -> SearchResult {
+            unreachable!("Stacker never gets past the setup phase");
+        }
+        fn get_placement_result(&self, _: Request) -> PlacementResult {
+            PlacementResult {
+                squares: [C3, C3],
+                eval: None,
+                time_spent: None,
+            }
+        }
+    }
+
+    #[test]
+    fn engine_specs() {
+        assert_eq!(create_engine("flop").unwrap().get_info().name, "flop");
+        assert!(create_engine("flip").is_err());
+        assert!(create_engine("cmd:").is_err());
+        assert!(create_engine("cmd:./there-is-no-such-engine").is_err());
+    }
+
+    #[test]
+    fn panic_is_forfeit() {
+        let result = run_game([Box::new(Crasher), get_engine("flop").unwrap()], default_start(), TimeControl::sudden_death(Duration::from_secs(1)));
+        assert_eq!(result.winner, P2);
+        assert_eq!(result.reason, GameEndReason::EngineCrashed(String::from("Crasher always crashes")));
+        assert_eq!(result.history.get_ply(), 0);
+    }
+
+    #[test]
+    fn overrun_is_forfeit() {
+        let result = run_game([Box::new(Sleeper), get_engine("flop").unwrap()], default_start(), TimeControl::sudden_death(Duration::from_millis(50)));
+        assert_eq!(result.winner, P2);
+        assert_eq!(result.reason, GameEndReason::TimeForfeit);
+    }
+
+    #[test]
+    fn illegal_move_is_forfeit() {
+        let result = run_game([Box::new(Cheater), get_engine("flop").unwrap()], default_start(), TimeControl::sudden_death(Duration::from_secs(1)));
+        assert_eq!(result.winner, P2);
+        assert!(matches!(result.reason, GameEndReason::IllegalMove(Move { from: B3, to: C4, .. }, RulesError::Occupied(C4))));
+    }
+
+    #[test]
+    fn illegal_placement_is_forfeit() {
+        let result = run_game([get_engine("flop").unwrap(), Box::new(Stacker)], Board::empty(), TimeControl::sudden_death(Duration::from_secs(1)));
+        assert_eq!(result.winner, P1);
+        assert_eq!(result.reason, GameEndReason::IllegalPlacement([C3, C3], RulesError::Occupied(C3)));
+        assert_eq!(result.history.get_placements().len(), 1);
+    }
+
+    #[test]
+    fn game_from_scratch() {
+        let result = run_game([get_engine("spectre").unwrap(), get_engine("flop").unwrap()], Board::empty(), TimeControl::sudden_death(Duration::from_secs(4)));
+        let placements = result.history.get_placements();
+        assert_eq!(placements.len(), 2);
+        assert!(placements[0].iter().all(|x| !placements[1].contains(x)));
+        assert!(!result.history.get_moves().is_empty());
+
+        // Spectre reports evals, flop doesn't place with a search
+        let plies = result.history.get_plies();
+        assert!(plies[0].1.eval.is_some() && plies[1].1.eval.is_none());
+        assert!(plies.iter().all(|(_, info)| info.time_spent.is_some()));
+    }
+}
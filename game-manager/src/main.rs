@@ -1,12 +1,171 @@
+mod clock;
 mod game;
+mod openings;
+mod record;
+mod sprt;
+mod tournament;
 
-use std::{io::{stdin, stdout, Write}, time::Duration};
+use std::{io::{stdin, stdout, Write}, path::{Path, PathBuf}, time::Duration};
 
 use engines::*;
 
-use crate::game::run_game;
+use crate::clock::TimeControl;
+use crate::game::{create_engine, run_game};
+use crate::openings::{load_openings, Openings};
+use crate::record::{append_record, draw_board, load_records, GameRecord};
+use crate::sprt::{run_sprt, SprtSettings};
+use crate::tournament::{run_tournament, Pairing, TournamentSettings};
 
-fn main() -> std::io::Result<()> {
+const USAGE: &str = "Usage:
+    game-manager
+        Pick two engines at the prompt and play one game from an empty board, starting
+        with each player placing their workers.
+    game-manager tournament [--games N] [--time CONTROL] [--gauntlet ENGINE] [ENGINE...]
+        Play every pairing of the given engines (all registered engines by default)
+        N times with colours swapped, then print a crosstable. With --gauntlet only
+        ENGINE's pairings are played.
+    game-manager sprt ENGINE BASELINE [--elo0 E0] [--elo1 E1] [--alpha A] [--beta B]
+                      [--time CONTROL] [--max-pairs N]
+        Play pairs of games with colours swapped until a sequential probability ratio
+        test accepts H1 (ENGINE is E1 Elo stronger) or H0 (it is at most E0 stronger).
+        Defaults: elo0 0, elo1 10, alpha 0.05, beta 0.05, time 10.
+    game-manager replay FILE
+        Print every position of the games recorded in FILE.
+
+Time controls are given in seconds per player: \"60\" for the whole game, \"60+0.5\" adding
+half a second after every ply, \"40/60\" adding another 60 seconds every 40 plies, which can
+have an increment too, or \"5/move\" for 5 seconds per ply. Tournaments default to 60.
+
+Every game played is appended to games.sgn, or to the file given with --record FILE.
+
+Engines are registry names, or \"cmd:\" followed by the command line of a program speaking
+the engine protocol, e.g. \"cmd:./old/engine-deploy spectre\" to play against another build.
+
+Tournaments and SPRT play each opening twice with colours swapped. By default the only
+opening is the standard start, which can be changed with:
+    --openings FILE     One opening per line: P1's and P2's worker squares, optionally
+                        followed by the 25 block heights from A1 to E5 and the side to move,
+                        e.g. \"B2 B4 D2 D4 0000000100001000000000000 P2\", or a position
+                        in board notation, e.g. \"00000/00100/00100/00000/00000 B2,B4 D2,D4 P2\".
+    --random-plies N    Openings made of N random legal plies from the standard start.";
+
+const DEFAULT_RECORD: &str = "games.sgn";
+
+type Options = Vec<(String, String)>;
+
+// Splits `--key value` pairs from positional arguments
+fn parse_options(args: &[String]) -> Result<(Options, Vec<String>), String> {
+    let mut options = vec![];
+    let mut positional = vec![];
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        if let Some(key) = arg.strip_prefix("--") {
+            let value = args.next().ok_or(format!("Missing value for --{}", key))?;
+            options.push((key.to_string(), value.clone()));
+        } else {
+            positional.push(arg.clone());
+        }
+    }
+    Ok((options, positional))
+}
+
+fn parse_number(key: &str, value: &str) -> Result<usize, String> {
+    value.parse().map_err(|_| format!("--{} expects a number, got {}", key, value))
+}
+
+fn parse_float(key: &str, value: &str) -> Result<f64, String> {
+    value.parse().map_err(|_| format!("--{} expects a number, got {}", key, value))
+}
+
+fn tournament_settings(args: &[String]) -> Result<TournamentSettings, String> {
+    let (options, mut names) = parse_options(args)?;
+
+    if names.is_empty() {
+        names = get_engine_names().into_iter().map(String::from).collect();
+        names.sort();
+    }
+    for name in &names {
+        create_engine(name)?;
+    }
+
+    let mut settings = TournamentSettings {
+        engines: names,
+        pairing: Pairing::RoundRobin,
+        games_per_pair: 2,
+        time: TimeControl::sudden_death(Duration::from_secs(60)),
+        openings: Openings::default(),
+        record: Some(PathBuf::from(DEFAULT_RECORD)),
+    };
+    for (key, value) in options {
+        match key.as_str() {
+            "record" => settings.record = Some(PathBuf::from(value)),
+            "games" => settings.games_per_pair = parse_number(&key, &value)?,
+            "time" => settings.time = value.parse().map_err(|x| format!("--time: {}", x))?,
+            "openings" => settings.openings = Openings::Fixed(load_openings(Path::new(&value))?),
+            "random-plies" => settings.openings = Openings::RandomPlies(parse_number(&key, &value)?),
+            "gauntlet" => {
+                if !settings.engines.contains(&value) {
+                    settings.engines.push(value.clone());
+                }
+                settings.pairing = Pairing::Gauntlet(value);
+            }
+            _ => return Err(format!("Unknown option --{}", key)),
+        }
+    }
+    if settings.engines.len() < 2 {
+        return Err(String::from("A tournament needs at least two engines"));
+    }
+
+    Ok(settings)
+}
+
+fn sprt_settings(args: &[String]) -> Result<SprtSettings, String> {
+    let (options, names) = parse_options(args)?;
+
+    let engines: [String ; 2] = names.try_into()
+        .map_err(|_| String::from("SPRT needs exactly two engines"))?;
+    for name in &engines {
+        create_engine(name)?;
+    }
+
+    let mut settings = SprtSettings {
+        engines,
+        elo0: 0.0,
+        elo1: 10.0,
+        alpha: 0.05,
+        beta: 0.05,
+        time: TimeControl::sudden_death(Duration::from_secs(10)),
+        max_pairs: None,
+        openings: Openings::default(),
+        record: Some(PathBuf::from(DEFAULT_RECORD)),
+    };
+    for (key, value) in options {
+        match key.as_str() {
+            "record" => settings.record = Some(PathBuf::from(value)),
+            "elo0" => settings.elo0 = parse_float(&key, &value)?,
+            "elo1" => settings.elo1 = parse_float(&key, &value)?,
+            "alpha" => settings.alpha = parse_float(&key, &value)?,
+            "beta" => settings.beta = parse_float(&key, &value)?,
+            "time" => settings.time = value.parse().map_err(|x| format!("--time: {}", x))?,
+            "max-pairs" => settings.max_pairs = Some(parse_number(&key, &value)?),
+            "openings" => settings.openings = Openings::Fixed(load_openings(Path::new(&value))?),
+            "random-plies" => settings.openings = Openings::RandomPlies(parse_number(&key, &value)?),
+            _ => return Err(format!("Unknown option --{}", key)),
+        }
+    }
+    if settings.elo0 >= settings.elo1 {
+        return Err(String::from("elo0 must be lower than elo1"));
+    }
+    // Error rates of 0 would give infinite bounds, and the test could never end
+    let is_error_rate = |x: f64| x > 0.0 && x < 1.0;
+    if !is_error_rate(settings.alpha) || !is_error_rate(settings.beta) {
+        return Err(String::from("alpha and beta must be between 0 and 1"));
+    }
+
+    Ok(settings)
+}
+
+fn play_interactive() -> std::io::Result<()> {
     let mut buffer = String::new();
 
     println!("{:?}", get_engine_names());
@@ -18,10 +177,9 @@ fn main() -> std::io::Result<()> {
         buffer.clear();
         stdin().read_line(&mut buffer)?;
 
-        if let Some(engine) = get_engine(buffer.trim_end()) {
-            break engine;
-        } else {
-            println!("Invalid name!");
+        match create_engine(buffer.trim_end()) {
+            Ok(engine) => break engine,
+            Err(message) => eprintln!("{}", message),
         }
     };
     let engine2 = loop {
@@ -31,14 +189,78 @@ fn main() -> std::io::Result<()> {
         buffer.clear();
         stdin().read_line(&mut buffer)?;
 
-        if let Some(engine) = get_engine(buffer.trim_end()) {
-            break engine;
-        } else {
-            println!("Invalid name!");
+        match create_engine(buffer.trim_end()) {
+            Ok(engine) => break engine,
+            Err(message) => eprintln!("{}", message),
+        }
+    };
+
+    let names = [engine1.get_info().name, engine2.get_info().name];
+    let time = TimeControl::sudden_death(Duration::from_secs(60));
+    let result = run_game([engine1, engine2], Board::empty(), time);
+
+    for (ply, (played, info)) in result.history.get_plies().iter().enumerate() {
+        match info.eval {
+            Some(eval) => println!("{}. {} ({})", ply + 1, played, eval),
+            None => println!("{}. {}", ply + 1, played),
+        }
+    }
+    println!("{} wins! Reason: {}", result.winner, result.reason);
+    println!("Time used: P1 {:?}, P2 {:?}", result.time_used[P1], result.time_used[P2]);
+
+    let record = GameRecord::from_game([&names[P1], &names[P2]], time, Board::empty(), &result);
+    if let Err(message) = append_record(Path::new(DEFAULT_RECORD), &record) {
+        eprintln!("{}", message);
+    }
+
+    Ok(())
+}
+
+fn replay(args: &[String]) -> Result<(), String> {
+    let [path] = args else {
+        return Err(String::from("replay needs exactly one file"));
+    };
+
+    for record in load_records(Path::new(path))? {
+        println!("{} (P1) vs {} (P2)", record.engines[P1], record.engines[P2]);
+
+        let plies: Vec<String> = record.placements
+            .iter()
+            .map(|x| format!("{},{}", x[0], x[1]))
+            .chain(record.moves.iter().map(|x| x.to_string()))
+            .collect();
+        let positions = record.positions()?;
+
+        println!("{}\n", draw_board(&positions[0]));
+        for (ply, board) in plies.iter().zip(&positions[1..]) {
+            println!("{}\n{}\n", ply, draw_board(board));
+        }
+        match record.winner {
+            Some(winner) => println!("{} wins! Reason: {}\n", winner, record.termination),
+            None => println!("Unfinished\n"),
         }
+    }
+
+    Ok(())
+}
+
+fn main() -> std::io::Result<()> {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+
+    let result = match args.first().map(|x| x.as_str()) {
+        None => return play_interactive(),
+        Some("tournament") => tournament_settings(&args[1..])
+            .map(|settings| print!("{}", run_tournament(settings))),
+        Some("sprt") => sprt_settings(&args[1..])
+            .map(|settings| println!("{}", run_sprt(settings))),
+        Some("replay") => replay(&args[1..]),
+        Some(mode) => Err(format!("Unknown mode {}", mode)),
     };
 
-    run_game([engine1, engine2], Duration::from_secs(60));
+    if let Err(message) = result {
+        eprintln!("{}\n{}", message, USAGE);
+        std::process::exit(2);
+    }
 
     Ok(())
 }
@@ -0,0 +1,153 @@
+use engines::*;
+use rand::{rngs::StdRng, seq::SliceRandom, SeedableRng};
+use std::{fs, path::Path};
+
+pub fn default_start() -> Board {
+    Board::new([B3, C2], [C4, D3])
+}
+
+// Start positions for matches. Every opening is meant to be played twice, once with each
+// engine as P1, so deterministic engines still produce different games.
+pub enum Openings {
+    Fixed(Vec<Board>),
+    // Positions reached after this many random legal plies from the default start. The
+    // same index always gives the same position, so every pairing sees the same openings.
+    RandomPlies(usize),
+}
+impl Openings {
+    pub fn get(&self, index: usize) -> Board {
+        match self {
+            Openings::Fixed(boards) => boards[index % boards.len()],
+            Openings::RandomPlies(plies) => random_opening(*plies, index as u64),
+        }
+    }
+}
+impl Default for Openings {
+    fn default() -> Self {
+        Openings::Fixed(vec![default_start()])
+    }
+}
+
+// One opening per line, `#` starts a comment. A line holds P1's two worker squares, P2's two
+// worker squares, then optionally the 25 block heights from A1 to E5 and the side to move:
+//
+//     B3 C2 C4 D3
+//     B2 B4 D2 D4 0000000100001000000000000 P2
+//
+// Lines in the position notation of Board::to_notation are accepted too.
+pub fn parse_opening(line: &str) -> Result<Board, String> {
+    if line.contains('/') {
+        return line.parse();
+    }
+
+    let tokens: Vec<&str> = line.split_whitespace().collect();
+    if tokens.len() < 4 {
+        return Err(format!("Expected four worker squares in \"{}\"", line));
+    }
+
+    let squares = tokens[..4]
+        .iter()
+        .map(|x| x.parse::<Square>())
+        .collect::<Result<Vec<Square>, String>>()?;
+
+    let mut blocks = [T0 ; 25];
+    let mut turn = P1;
+    for token in &tokens[4..] {
+        if let Ok(parsed) = token.parse::<Turn>() {
+            turn = parsed;
+        } else if token.len() == 25 {
+            for (square, height) in token.chars().enumerate() {
+                let height = height.to_digit(10).ok_or(format!("Invalid height {} in \"{}\"", height, line))?;
+                blocks[square] = Blocks::try_from(height as usize)?;
+            }
+        } else {
+            return Err(format!("Unexpected {} in \"{}\"", token, line));
+        }
+    }
+
+    Board::try_new_custom(blocks, [squares[0], squares[1]], [squares[2], squares[3]], turn)
+        .map_err(|x| format!("{} in \"{}\"", x, line))
+}
+
+pub fn load_openings(path: &Path) -> Result<Vec<Board>, String> {
+    let contents = fs::read_to_string(path).map_err(|x| format!("Can't read {}: {}", path.display(), x))?;
+
+    let boards = contents
+        .lines()
+        .map(|line| line.split('#').next().unwrap().trim())
+        .filter(|line| !line.is_empty())
+        .map(parse_opening)
+        .collect::<Result<Vec<Board>, String>>()?;
+
+    if boards.is_empty() {
+        return Err(format!("{} has no openings", path.display()));
+    }
+    Ok(boards)
+}
+
+fn random_opening(plies: usize, seed: u64) -> Board {
+    let mut rng = StdRng::seed_from_u64(seed);
+
+    // Random play can stumble into a win, in which case we start over
+    loop {
+        let mut board = default_start();
+        for _ in 0..plies {
+            let moves = board.legal_moves();
+            board.apply_move(*moves.choose(&mut rng).unwrap());
+            if board.get_victory().is_some() {
+                break;
+            }
+        }
+        if board.get_victory().is_none() {
+            return board;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_workers_only() {
+        let board = parse_opening("B3 C2 C4 D3").unwrap();
+        assert_eq!(board.get_workers()[B3], Some(Worker { turn: P1 }));
+        assert_eq!(board.get_workers()[D3], Some(Worker { turn: P2 }));
+        assert_eq!(board.get_turn(), P1);
+    }
+
+    #[test]
+    fn parse_blocks_and_turn() {
+        let board = parse_opening("b2 b4 d2 d4 0000000100001000000000004 P2").unwrap();
+        assert_eq!(board.get_blocks()[B3], T1);
+        assert_eq!(board.get_blocks()[C3], T1);
+        assert_eq!(board.get_blocks()[E5], T4);
+        assert_eq!(board.get_turn(), P2);
+    }
+
+    #[test]
+    fn parse_notation() {
+        let board = parse_opening("00000/00100/00000/00000/00004 B2,B4 D2,D4 P2").unwrap();
+        assert_eq!(board, parse_opening("b2 b4 d2 d4 0000000100000000000000004 P2").unwrap());
+    }
+
+    #[test]
+    fn parse_errors() {
+        assert!(parse_opening("B3 C2 C4").is_err());
+        assert!(parse_opening("B3 C2 C4 F3").is_err());
+        assert!(parse_opening("B3 B3 C4 D3").is_err());
+        assert!(parse_opening("B3 C2 C4 D3 P3").is_err());
+        assert!(parse_opening("B3 C2 C4 D3 00000004").is_err());
+        assert!(parse_opening("B3 C2 C4 D3 0000000300000000000000000").is_err());
+    }
+
+    #[test]
+    fn random_openings_are_reproducible() {
+        let openings = Openings::RandomPlies(6);
+        let first = openings.get(3);
+        let again = openings.get(3);
+        assert_eq!(first.get_blocks(), again.get_blocks());
+        assert_eq!(first.get_workers(), again.get_workers());
+        assert!(first.get_blocks().iter().any(|&x| x != T0));
+    }
+}
@@ -0,0 +1,306 @@
+use engines::*;
+use std::{
+    fmt::Display,
+    fs::{self, OpenOptions},
+    io::Write,
+    path::Path,
+    time::Duration,
+};
+
+use crate::clock::TimeControl;
+use crate::game::GameResult;
+
+// A finished game in a PGN-like text format. Tags come first, then the numbered plies:
+//
+//     [P1 "spectre"]
+//     [P2 "flop"]
+//     [Time "60+0.5"]
+//     [Start "00000/00000/00000/00000/00000 - - P1"]
+//     [Result "1-0"]
+//     [Termination "reached the third level"]
+//
+//     1. C3,B3 C2,D4 2. B3-B4^B5 D4-D3^D2 3. ... 9. C3-D4 1-0
+//
+// Placements are written as two squares joined by a comma, moves as from-to^build with the
+// build left out of winning moves. The start position uses the notation of Board::to_notation
+// and the time control the notation of TimeControl.
+#[derive(Clone, Debug)]
+pub struct GameRecord {
+    pub engines: [String ; 2],
+    pub time: TimeControl,
+    pub start: Board,
+    // None when the game wasn't finished
+    pub winner: Option<Turn>,
+    pub termination: String,
+    pub placements: Vec<[Square ; 2]>,
+    pub moves: Vec<Move>,
+}
+impl GameRecord {
+    pub fn from_game(engines: [&str ; 2], time: TimeControl, start: Board, result: &GameResult) -> Self {
+        GameRecord {
+            engines: engines.map(String::from),
+            time,
+            start,
+            winner: Some(result.winner),
+            termination: result.reason.to_string(),
+            placements: result.history.get_placements(),
+            moves: result.history.get_moves(),
+        }
+    }
+
+    // Every position of the game, from the start to the one after the last ply
+    pub fn positions(&self) -> Result<Vec<Board>, String> {
+        let mut positions = vec![self.start];
+        let mut board = self.start;
+
+        for (ply, squares) in self.placements.iter().enumerate() {
+            board.try_place_workers(*squares).map_err(|x| format!("Ply {}: {}", ply + 1, x))?;
+            positions.push(board);
+        }
+        for (ply, mv) in self.moves.iter().enumerate() {
+            board.try_apply_move(*mv).map_err(|x| format!("Ply {}: {}", self.placements.len() + ply + 1, x))?;
+            positions.push(board);
+        }
+
+        Ok(positions)
+    }
+}
+impl Display for GameRecord {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "[P1 \"{}\"]", self.engines[P1])?;
+        writeln!(f, "[P2 \"{}\"]", self.engines[P2])?;
+        writeln!(f, "[Time \"{}\"]", self.time)?;
+        writeln!(f, "[Start \"{}\"]", self.start.to_notation())?;
+        writeln!(f, "[Result \"{}\"]", format_result(self.winner))?;
+        writeln!(f, "[Termination \"{}\"]", self.termination)?;
+        writeln!(f)?;
+
+        let plies = self.placements
+            .iter()
+            .map(|x| format!("{},{}", x[0], x[1]))
+            .chain(self.moves.iter().map(|x| x.to_string()));
+
+        let mut turn = self.start.get_turn();
+        let mut number = 1;
+        let mut tokens = vec![];
+        for (ply, text) in plies.enumerate() {
+            if turn == P1 {
+                tokens.push(format!("{}.", number));
+            } else if ply == 0 {
+                tokens.push(format!("{}...", number));
+            }
+            tokens.push(text);
+            if turn == P2 {
+                number += 1;
+            }
+            turn = turn.next();
+        }
+        tokens.push(format_result(self.winner));
+
+        writeln!(f, "{}", tokens.join(" "))
+    }
+}
+
+fn format_result(winner: Option<Turn>) -> String {
+    String::from(match winner {
+        Some(P1) => "1-0",
+        Some(P2) => "0-1",
+        None => "*",
+    })
+}
+
+fn parse_result(text: &str) -> Result<Option<Turn>, String> {
+    match text {
+        "1-0" => Ok(Some(P1)),
+        "0-1" => Ok(Some(P2)),
+        "*" => Ok(None),
+        _ => Err(format!("{} is not a valid result! Results are 1-0, 0-1 and *.", text)),
+    }
+}
+
+fn parse_tag(line: &str) -> Result<(&str, &str), String> {
+    let (name, value) = line
+        .strip_prefix('[')
+        .and_then(|x| x.strip_suffix(']'))
+        .and_then(|x| x.split_once(' '))
+        .ok_or(format!("Invalid tag {}", line))?;
+    let value = value
+        .trim()
+        .strip_prefix('"')
+        .and_then(|x| x.strip_suffix('"'))
+        .ok_or(format!("Tag values must be quoted in {}", line))?;
+    Ok((name, value))
+}
+
+pub fn parse_record(text: &str) -> Result<GameRecord, String> {
+    let mut record = GameRecord {
+        engines: [String::new(), String::new()],
+        time: TimeControl::sudden_death(Duration::ZERO),
+        start: Board::empty(),
+        winner: None,
+        termination: String::new(),
+        placements: vec![],
+        moves: vec![],
+    };
+
+    let mut plies = vec![];
+    for line in text.lines().map(|x| x.trim()).filter(|x| !x.is_empty()) {
+        if !line.starts_with('[') {
+            plies.extend(line.split_whitespace());
+            continue;
+        }
+
+        let (name, value) = parse_tag(line)?;
+        match name {
+            "P1" => record.engines[P1] = value.to_string(),
+            "P2" => record.engines[P2] = value.to_string(),
+            "Time" => record.time = value.parse()?,
+            "Start" => record.start = value.parse()?,
+            "Result" => record.winner = parse_result(value)?,
+            "Termination" => record.termination = value.to_string(),
+            // Unknown tags are allowed so other tools can add their own
+            _ => {}
+        }
+    }
+
+    for ply in plies {
+        if ply.ends_with('.') || parse_result(ply).is_ok() {
+            continue;
+        }
+        if let Some((first, second)) = ply.split_once(',') {
+            if !record.moves.is_empty() {
+                return Err(format!("Placement {} after the first move", ply));
+            }
+            record.placements.push([first.parse()?, second.parse()?]);
+        } else {
+            record.moves.push(ply.parse()?);
+        }
+    }
+
+    Ok(record)
+}
+
+// Files can hold several games, each one starting with its tags
+pub fn load_records(path: &Path) -> Result<Vec<GameRecord>, String> {
+    let contents = fs::read_to_string(path).map_err(|x| format!("Can't read {}: {}", path.display(), x))?;
+
+    let mut games: Vec<String> = vec![];
+    let mut in_tags = false;
+    for line in contents.lines() {
+        let is_tag = line.trim_start().starts_with('[');
+        if is_tag && !in_tags {
+            games.push(String::new());
+        }
+        in_tags = is_tag || (in_tags && line.trim().is_empty());
+        if let Some(game) = games.last_mut() {
+            game.push_str(line);
+            game.push('\n');
+        }
+    }
+
+    games.iter().map(|x| parse_record(x)).collect()
+}
+
+pub fn append_record(path: &Path, record: &GameRecord) -> Result<(), String> {
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .map_err(|x| format!("Can't open {}: {}", path.display(), x))?;
+    writeln!(file, "{}", record).map_err(|x| format!("Can't write to {}: {}", path.display(), x))
+}
+
+// Heights by square with the owner of any worker standing there, A1 in the top left
+pub fn draw_board(board: &Board) -> String {
+    let mut rows = vec![];
+    for row in Square::squares().chunks(5) {
+        let cells: Vec<String> = row
+            .iter()
+            .map(|&square| {
+                let worker = match board.get_workers()[square] {
+                    Some(Worker { turn: P1 }) => 'x',
+                    Some(Worker { turn: P2 }) => 'o',
+                    None => ' ',
+                };
+                format!("{}{}", usize::from(board.get_blocks()[square]), worker)
+            })
+            .collect();
+        rows.push(cells.join(" "));
+    }
+    rows.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::GameEndReason;
+
+    fn sample_record() -> GameRecord {
+        GameRecord {
+            engines: [String::from("spectre"), String::from("flop")],
+            time: "60+0.5".parse().unwrap(),
+            start: Board::empty(),
+            winner: Some(P2),
+            termination: GameEndReason::TimeForfeit.to_string(),
+            placements: vec![[C3, B3], [C2, D4]],
+            moves: vec![Move { from: B3, to: B4, at: Some(B5) }, Move { from: D4, to: D3, at: Some(D2) }],
+        }
+    }
+
+    #[test]
+    fn round_trip() {
+        let record = sample_record();
+        let text = record.to_string();
+        assert!(text.contains("1. C3,B3 C2,D4 2. B3-B4^B5 D4-D3^D2 0-1"));
+
+        let parsed = parse_record(&text).unwrap();
+        assert_eq!(parsed.engines, record.engines);
+        assert_eq!(parsed.time, record.time);
+        assert_eq!(parsed.start, record.start);
+        assert_eq!(parsed.winner, record.winner);
+        assert_eq!(parsed.termination, "time forfeit");
+        assert_eq!(parsed.placements, record.placements);
+        assert_eq!(parsed.moves, record.moves);
+    }
+
+    #[test]
+    fn replay() {
+        let positions = sample_record().positions().unwrap();
+        assert_eq!(positions.len(), 5);
+        assert!(positions[2].get_workers()[D4].is_some());
+        assert_eq!(positions[4].get_blocks()[D2], T1);
+        assert_eq!(positions[4].get_turn(), P1);
+    }
+
+    #[test]
+    fn replay_rejects_illegal_moves() {
+        let mut record = sample_record();
+        record.moves.push(Move { from: B4, to: C3, at: Some(B4) });
+        assert!(record.positions().unwrap_err().starts_with("Ply 5"));
+    }
+
+    #[test]
+    fn numbering_when_p2_starts() {
+        let mut record = sample_record();
+        record.start = "00000/00000/00000/00000/00000 B3,C2 C4,D3 P2".parse().unwrap();
+        record.placements.clear();
+        record.moves = vec![Move { from: C4, to: C5, at: Some(B5) }];
+
+        let text = record.to_string();
+        assert!(text.contains("1... C4-C5^B5 0-1"));
+        assert_eq!(parse_record(&text).unwrap().positions().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn several_games() {
+        let path = std::env::temp_dir().join(format!("records-{}.sgn", std::process::id()));
+        let _ = fs::remove_file(&path);
+        append_record(&path, &sample_record()).unwrap();
+        append_record(&path, &sample_record()).unwrap();
+
+        let records = load_records(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[1].moves.len(), 2);
+    }
+}
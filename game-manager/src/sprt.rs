@@ -0,0 +1,230 @@
+use std::{fmt::Display, path::PathBuf};
+
+use crate::clock::TimeControl;
+use crate::openings::Openings;
+use crate::tournament::play_pair;
+
+pub struct SprtSettings {
+    // The engine under test first, the baseline second
+    pub engines: [String ; 2],
+    pub elo0: f64,
+    pub elo1: f64,
+    pub alpha: f64,
+    pub beta: f64,
+    pub time: TimeControl,
+    pub max_pairs: Option<usize>,
+    pub openings: Openings,
+    pub record: Option<PathBuf>,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Verdict {
+    // The engine under test is at least elo1 stronger
+    AcceptH1,
+    // The engine under test is at most elo0 stronger
+    AcceptH0,
+    // max_pairs was reached before either bound
+    Inconclusive,
+}
+
+// Games are played in pairs with colours swapped, which cancels most of the first player
+// advantage. pairs[k] is how many pairs the engine under test won k games in.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct SprtStatus {
+    pub pairs: [usize ; 3],
+}
+impl SprtStatus {
+    pub fn total_pairs(&self) -> usize {
+        self.pairs.iter().sum()
+    }
+    // Mean and variance of the pair score (0, 1/2 or 1)
+    fn score_stats(&self) -> (f64, f64) {
+        let total = self.total_pairs() as f64;
+        let mean = (0..3).map(|k| self.pairs[k] as f64 * PAIR_SCORES[k]).sum::<f64>() / total;
+        let variance = (0..3).map(|k| self.pairs[k] as f64 * (PAIR_SCORES[k] - mean).powi(2)).sum::<f64>() / total;
+        (mean, variance)
+    }
+    // Log-likelihood ratio of H1 (elo1) against H0 (elo0). The two games of a pair share an
+    // opening and aren't independent, so the samples are pair scores. Each hypothesis fixes
+    // the mean score, and the distribution of the three outcomes is the most likely one with
+    // that mean (the generalized SPRT).
+    pub fn llr(&self, elo0: f64, elo1: f64) -> f64 {
+        let p0 = pair_distribution(self.pairs, expected_score(elo0));
+        let p1 = pair_distribution(self.pairs, expected_score(elo1));
+        (0..3).filter(|&k| self.pairs[k] > 0).map(|k| self.pairs[k] as f64 * (p1[k] / p0[k]).ln()).sum()
+    }
+    // Elo estimate with its 95% confidence interval, from the spread of the pair scores
+    pub fn elo(&self) -> (f64, f64, f64) {
+        let total = self.total_pairs() as f64;
+        if total == 0.0 {
+            return (0.0, f64::NEG_INFINITY, f64::INFINITY);
+        }
+        let (score, variance) = self.score_stats();
+        let margin = 1.96 * (variance / total).sqrt();
+        (score_to_elo(score), score_to_elo(score - margin), score_to_elo(score + margin))
+    }
+}
+
+pub struct SprtReport {
+    pub settings: SprtSettings,
+    pub status: SprtStatus,
+    pub verdict: Verdict,
+}
+impl Display for SprtReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let SprtSettings { engines, elo0, elo1, alpha, beta, .. } = &self.settings;
+        let (lower, upper) = llr_bounds(*alpha, *beta);
+        let (elo, elo_low, elo_high) = self.status.elo();
+
+        writeln!(f, "SPRT {} vs {}, elo0 {} elo1 {} alpha {} beta {}", engines[0], engines[1], elo0, elo1, alpha, beta)?;
+        writeln!(f, "Pairs: {} (2-0: {}, 1-1: {}, 0-2: {})",
+            self.status.total_pairs(), self.status.pairs[2], self.status.pairs[1], self.status.pairs[0])?;
+        writeln!(f, "LLR: {:.2} ({:.2}, {:.2})", self.status.llr(*elo0, *elo1), lower, upper)?;
+        writeln!(f, "Elo: {:.1} [{:.1}, {:.1}]", elo, elo_low, elo_high)?;
+        write!(f, "Verdict: {}", match self.verdict {
+            Verdict::AcceptH1 => format!("H1 accepted, {} is stronger", engines[0]),
+            Verdict::AcceptH0 => format!("H0 accepted, {} is not stronger", engines[0]),
+            Verdict::Inconclusive => String::from("inconclusive"),
+        })
+    }
+}
+
+const PAIR_SCORES: [f64 ; 3] = [0.0, 0.5, 1.0];
+
+// Most likely distribution of the pair outcomes given the counts, constrained to a mean score
+// of score. It has the form p[k] = f[k] / (1 + lambda * (x[k] - score)), and lambda is found by
+// bisection. The counts are nudged away from zero so that any mean can be reached.
+fn pair_distribution(pairs: [usize ; 3], score: f64) -> [f64 ; 3] {
+    let total = pairs.iter().sum::<usize>() as f64 + 3e-3;
+    let freqs = pairs.map(|n| (n as f64 + 1e-3) / total);
+    let excess = PAIR_SCORES.map(|x| x - score);
+    let distribution = |lambda: f64| -> [f64 ; 3] {
+        [0, 1, 2].map(|k| freqs[k] / (1.0 + lambda * excess[k]))
+    };
+
+    // The mean of the distribution decreases with lambda, which has to keep every term positive
+    let (mut low, mut high) = (-1.0 / (1.0 - score), 1.0 / score);
+    for _ in 0..100 {
+        let lambda = (low + high) / 2.0;
+        let p = distribution(lambda);
+        let mean_excess: f64 = (0..3).map(|k| p[k] * excess[k]).sum();
+        if mean_excess > 0.0 {
+            low = lambda;
+        } else {
+            high = lambda;
+        }
+    }
+    distribution((low + high) / 2.0)
+}
+
+fn expected_score(elo: f64) -> f64 {
+    1.0 / (1.0 + 10f64.powf(-elo / 400.0))
+}
+
+fn score_to_elo(score: f64) -> f64 {
+    let score = score.clamp(1e-6, 1.0 - 1e-6);
+    -400.0 * (1.0 / score - 1.0).log10()
+}
+
+fn llr_bounds(alpha: f64, beta: f64) -> (f64, f64) {
+    ((beta / (1.0 - alpha)).ln(), ((1.0 - beta) / alpha).ln())
+}
+
+// The engines are checked when the settings are made
+pub fn run_sprt(settings: SprtSettings) -> SprtReport {
+    let (lower, upper) = llr_bounds(settings.alpha, settings.beta);
+    let mut status = SprtStatus::default();
+
+    let verdict = loop {
+        if settings.max_pairs.is_some_and(|x| status.total_pairs() >= x) {
+            break Verdict::Inconclusive;
+        }
+
+        let mut won = 0;
+        let first_game = 2 * status.total_pairs();
+        for game in first_game..first_game + 2 {
+            let (players, result) = play_pair(&settings.engines[0], &settings.engines[1], &settings.openings, game, settings.time, settings.record.as_deref());
+            if players[result.winner] == 0 {
+                won += 1;
+            }
+        }
+        status.pairs[won] += 1;
+
+        let llr = status.llr(settings.elo0, settings.elo1);
+        println!("Pair {}: {}-{}, LLR {:.2} ({:.2}, {:.2})", status.total_pairs(), won, 2 - won, llr, lower, upper);
+
+        if llr >= upper {
+            break Verdict::AcceptH1;
+        }
+        if llr <= lower {
+            break Verdict::AcceptH0;
+        }
+    };
+
+    SprtReport {
+        settings,
+        status,
+        verdict,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn elo_of_even_score() {
+        let status = SprtStatus { pairs: [10, 20, 10] };
+        let (elo, low, high) = status.elo();
+        assert!(elo.abs() < 1e-9);
+        assert!(low < 0.0 && high > 0.0);
+    }
+
+    #[test]
+    fn elo_of_three_quarters() {
+        let status = SprtStatus { pairs: [0, 20, 20] };
+        assert!((status.elo().0 - 190.85).abs() < 0.01);
+    }
+
+    #[test]
+    fn llr_direction() {
+        let winning = SprtStatus { pairs: [10, 60, 130] };
+        let even = SprtStatus { pairs: [30, 40, 30] };
+        assert!(winning.llr(0.0, 10.0) > 0.0);
+        assert!(even.llr(0.0, 10.0) < 0.0);
+        assert!(winning.llr(0.0, 10.0) > llr_bounds(0.05, 0.05).1);
+    }
+
+    #[test]
+    fn llr_of_one_pair() {
+        let status = SprtStatus { pairs: [1, 0, 0] };
+        let (lower, _) = llr_bounds(0.05, 0.05);
+        assert!(status.llr(0.0, 10.0) < 0.0);
+        assert!(status.llr(0.0, 10.0) > lower);
+    }
+
+    #[test]
+    fn pairs_are_not_independent_games() {
+        // Same number of wins, but split pairs are much more consistent than 2-0s and 0-2s
+        let split = SprtStatus { pairs: [0, 40, 0] };
+        let swingy = SprtStatus { pairs: [20, 0, 20] };
+        let (_, split_low, split_high) = split.elo();
+        let (_, swingy_low, swingy_high) = swingy.elo();
+        assert!(split_high - split_low < swingy_high - swingy_low);
+        assert!(split.llr(0.0, 10.0) < swingy.llr(0.0, 10.0));
+    }
+
+    #[test]
+    fn pair_distribution_has_the_mean() {
+        let p = pair_distribution([5, 30, 65], 0.6);
+        assert!((p.iter().sum::<f64>() - 1.0).abs() < 1e-9);
+        assert!((p[1] * 0.5 + p[2] - 0.6).abs() < 1e-9);
+    }
+
+    #[test]
+    fn bounds() {
+        let (lower, upper) = llr_bounds(0.05, 0.05);
+        assert!((lower + 2.944).abs() < 0.001);
+        assert!((upper - 2.944).abs() < 0.001);
+    }
+}
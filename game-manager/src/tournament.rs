@@ -0,0 +1,173 @@
+use engines::*;
+use std::{fmt::Display, path::{Path, PathBuf}};
+
+use crate::clock::TimeControl;
+use crate::game::{create_engine, run_game, GameResult};
+use crate::openings::Openings;
+use crate::record::{append_record, GameRecord};
+
+pub enum Pairing {
+    // Every engine plays every other engine
+    RoundRobin,
+    // The named engine plays every other engine, the rest don't play each other
+    Gauntlet(String),
+}
+
+pub struct TournamentSettings {
+    pub engines: Vec<String>,
+    pub pairing: Pairing,
+    pub games_per_pair: usize,
+    pub time: TimeControl,
+    pub openings: Openings,
+    // Every finished game is appended to this file
+    pub record: Option<PathBuf>,
+}
+
+pub struct Crosstable {
+    pub names: Vec<String>,
+    // wins[i][j] is how many games engine i won against engine j
+    pub wins: Vec<Vec<usize>>,
+}
+impl Crosstable {
+    fn new(names: Vec<String>) -> Self {
+        let wins = vec![vec![0 ; names.len()] ; names.len()];
+        Crosstable { names, wins }
+    }
+    pub fn games_between(&self, i: usize, j: usize) -> usize {
+        self.wins[i][j] + self.wins[j][i]
+    }
+    pub fn score(&self, i: usize) -> usize {
+        self.wins[i].iter().sum()
+    }
+    pub fn games_played(&self, i: usize) -> usize {
+        (0..self.names.len()).map(|j| self.games_between(i, j)).sum()
+    }
+}
+impl Display for Crosstable {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let width = self.names.iter().map(|x| x.len()).max().unwrap_or(0).max(7);
+
+        write!(f, "{:width$}", "")?;
+        for name in &self.names {
+            write!(f, " {:>width$}", name)?;
+        }
+        writeln!(f, " {:>7} {:>8}", "Score", "Win rate")?;
+
+        let mut order: Vec<usize> = (0..self.names.len()).collect();
+        order.sort_by_key(|&i| std::cmp::Reverse(self.score(i)));
+
+        for i in order {
+            write!(f, "{:width$}", self.names[i])?;
+            for j in 0..self.names.len() {
+                let cell = if i == j || self.games_between(i, j) == 0 {
+                    String::from("-")
+                } else {
+                    format!("{}-{}", self.wins[i][j], self.wins[j][i])
+                };
+                write!(f, " {:>width$}", cell)?;
+            }
+            let played = self.games_played(i);
+            let win_rate = if played == 0 {
+                0.0
+            } else {
+                100.0 * self.score(i) as f64 / played as f64
+            };
+            writeln!(f, " {:>7} {:>7.1}%", format!("{}/{}", self.score(i), played), win_rate)?;
+        }
+
+        Ok(())
+    }
+}
+
+fn get_pairs(settings: &TournamentSettings) -> Vec<(usize, usize)> {
+    let count = settings.engines.len();
+    match &settings.pairing {
+        Pairing::RoundRobin => (0..count)
+            .flat_map(|i| (i + 1..count).map(move |j| (i, j)))
+            .collect(),
+        Pairing::Gauntlet(challenger) => {
+            let i = settings.engines.iter().position(|x| x == challenger)
+                .expect("The gauntlet challenger must be one of the tournament engines");
+            (0..count).filter(|&j| j != i).map(|j| (i, j)).collect()
+        }
+    }
+}
+
+// Plays game number `game` between two engines. Each opening is used for two consecutive
+// games with colours swapped, so every engine plays both sides of it.
+pub fn play_pair(first: &str, second: &str, openings: &Openings, game: usize, time: TimeControl, record: Option<&Path>) -> ([usize ; 2], GameResult) {
+    let players = if game.is_multiple_of(2) { [0, 1] } else { [1, 0] };
+    let names = [first, second];
+    let engines = players.map(|x| create_engine(names[x]).expect("Tournament engines are checked beforehand"));
+
+    let start = openings.get(game / 2);
+    let result = run_game(engines, start, time);
+
+    if let Some(path) = record {
+        let game_record = GameRecord::from_game(players.map(|x| names[x]), time, start, &result);
+        if let Err(message) = append_record(path, &game_record) {
+            eprintln!("{}", message);
+        }
+    }
+
+    (players, result)
+}
+
+// The engines are checked when the settings are made
+pub fn run_tournament(settings: TournamentSettings) -> Crosstable {
+    let mut crosstable = Crosstable::new(settings.engines.clone());
+
+    for (i, j) in get_pairs(&settings) {
+        for game in 0..settings.games_per_pair {
+            let (players, result) = play_pair(&settings.engines[i], &settings.engines[j], &settings.openings, game, settings.time, settings.record.as_deref());
+            let player_indexes = players.map(|x| [i, j][x]);
+            let winner = player_indexes[result.winner];
+            let loser = player_indexes[result.winner.next()];
+            crosstable.wins[winner][loser] += 1;
+
+            println!("{} (P1) vs {} (P2): {} wins in {} moves ({})",
+                settings.engines[player_indexes[P1]], settings.engines[player_indexes[P2]],
+                settings.engines[winner], result.history.get_moves().len(), result.reason);
+        }
+    }
+
+    crosstable
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    fn settings(pairing: Pairing) -> TournamentSettings {
+        TournamentSettings {
+            engines: vec![String::from("flop"), String::from("spectre"), String::from("strange")],
+            pairing,
+            games_per_pair: 2,
+            time: TimeControl::sudden_death(Duration::from_secs(1)),
+            openings: Openings::default(),
+            record: None,
+        }
+    }
+
+    #[test]
+    fn round_robin_pairs() {
+        assert_eq!(get_pairs(&settings(Pairing::RoundRobin)), vec![(0, 1), (0, 2), (1, 2)]);
+    }
+
+    #[test]
+    fn gauntlet_pairs() {
+        let pairs = get_pairs(&settings(Pairing::Gauntlet(String::from("spectre"))));
+        assert_eq!(pairs, vec![(1, 0), (1, 2)]);
+    }
+
+    #[test]
+    fn crosstable_scores() {
+        let mut crosstable = Crosstable::new(vec![String::from("a"), String::from("b")]);
+        crosstable.wins[0][1] = 3;
+        crosstable.wins[1][0] = 1;
+        assert_eq!(crosstable.score(0), 3);
+        assert_eq!(crosstable.games_played(1), 4);
+        assert!(crosstable.to_string().contains("3-1"));
+    }
+}